@@ -9,11 +9,16 @@ pub struct Args {
     #[clap(subcommand)]
     pub command: Commands,
     /// Input file. If absent or '-', reads standard input.
+    /// If a directory, runs the command on every blueprint in it (batch mode).
     #[clap(short, long)]
     pub input: Option<String>,
     /// Output file. If absent or '-', writes to standard output.
+    /// In batch mode, the directory to write outputs to, mirroring the input directory.
     #[clap(short, long)]
     pub output: Option<String>,
+    /// Number of files to process at once in batch mode. Defaults to the number of CPUs.
+    #[clap(short, long)]
+    pub jobs: Option<usize>,
     /// Compression level. Uses 6 by default, like DSP does. Set it to 9 for about 5% smaller
     /// blueprints that (almost certainly) still work fine.
     #[clap(short, long, default_value_t = 6)]
@@ -46,34 +51,284 @@ pub struct EditArgs {
     /// Replace icon text.
     #[clap(short = 't', long)]
     pub icon_text: Option<String>,
+    /// Replace the description. Use "\n" for line breaks.
+    #[clap(short = 'd', long)]
+    pub description: Option<String>,
+    /// Set the save time: "now" (local time), a date like "2024-01-31" or
+    /// "2024-01-31 12:30", or a raw tick count.
+    #[clap(long)]
+    pub timestamp: Option<String>,
+    /// Set the icon layout, the arrangement of icons in the blueprint browser.
+    #[clap(long)]
+    pub layout: Option<u32>,
+    /// Set a blueprint icon, like "1=IronIngot", "2=recipe:Gear", "3=signal:Number1" or "4=tech:1001".
+    ///
+    /// Slots are 1 to 5. An empty icon, like "5=", clears the slot. Can be repeated.
+    #[clap(long = "icon")]
+    pub icons: Vec<String>,
+    /// Set the first icon to the output of the recipe most buildings use.
+    #[clap(long)]
+    pub auto_icon: bool,
+    /// Set how production buildings use proliferated inputs: "extra_products" or "speedup".
+    #[clap(short = 'p', long)]
+    pub proliferator_mode: Option<String>,
+    /// Only edit buildings matching this selector, like "item=ArcSmelter and area=0".
+    ///
+    /// Conditions: item=, recipe=, area=, index=, "index in A..B", bbox=X1,Y1,X2,Y2, combined
+    /// with and, or, not and parentheses. Blueprint icons are left alone.
+    #[clap(short = 'w', long = "where")]
+    pub selector: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct ApplyArgs {
+    /// TOML script with the operations to run.
+    pub script: String,
+    /// Blueprint files or directories to edit instead of the script's inputs.
+    ///
+    /// Without inputs here or in the script, edits the input file.
+    pub inputs: Vec<String>,
 }
 
 #[derive(Parser, Debug)]
 #[clap()]
 pub struct DumpArgs {
     /// Use human readable names for IDs of various things.
-    /// Such ouput is informational only and CANNOT BE UNDUMPED.
+    ///
+    /// Such output can be edited and undumped. Names may be changed to any name other commands
+    /// accept, and recipe icons are written as "recipe:Name".
     #[clap(short = 'H', long, default_value_t = false)]
     pub human_readable: bool,
-    /// Locale to use. At the moment en and cn are supported. By default, en is used.
+    /// Locale to use, like en or cn. Only locales compiled in by update_data.py are available.
+    /// By default, en is used.
     #[clap(short = 'L', long)]
     pub locale: Option<String>,
 }
 
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct NamesArgs {
+    /// Also print names in this locale, like en or cn. Names missing from the locale are
+    /// printed in English.
+    #[clap(short = 'L', long)]
+    pub locale: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct VerifyArgs {
+    /// Verify every *.txt blueprint in this directory instead of the input file.
+    pub dir: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct TileArgs {
+    /// Number of copies along x.
+    #[clap(short = 'x', long, default_value_t = 1)]
+    pub columns: u32,
+    /// Number of copies along y.
+    #[clap(short = 'y', long, default_value_t = 1)]
+    pub rows: u32,
+    /// Distance between copies along x, in grid cells, at least 0. Defaults to the blueprint
+    /// width.
+    #[clap(long)]
+    pub dx: Option<f32>,
+    /// Distance between copies along y, in grid cells, at least 0. Defaults to the blueprint
+    /// height.
+    #[clap(long)]
+    pub dy: Option<f32>,
+    /// Connect belt ends of each copy to adjacent belt starts of the next copy.
+    ///
+    /// Copies are chained along rows, or along the column if there is only one column.
+    #[clap(short = 'C', long, default_value_t = false)]
+    pub chain_belts: bool,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct TierArgs {
+    /// Target tier, counted from 1 within each family. Defaults to the highest tier when
+    /// upgrading and the lowest when downgrading.
+    #[clap(short = 'T', long)]
+    pub tier: Option<usize>,
+    /// Only change these families, like "belt,sorter". See 'dspbp families'.
+    #[clap(short, long)]
+    pub families: Option<String>,
+    /// Only change buildings matching this selector. See 'dspbp help edit'.
+    #[clap(short = 'w', long = "where")]
+    pub selector: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct MoveArgs {
+    /// Target area index.
+    #[clap(short, long)]
+    pub to: i8,
+    /// Move all buildings of this area.
+    #[clap(short, long)]
+    pub from: Option<i8>,
+    /// Move buildings at these positions in the building list, like "0,5,6".
+    #[clap(short, long)]
+    pub buildings: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AreaCommands {
+    /// Move buildings to another area, keeping their position on the planet.
+    Move(MoveArgs),
+    /// Collapse all areas into the root area of the primary area.
+    Flatten,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct AreasArgs {
+    /// Without a subcommand, lists areas and checks that every building is in a real area.
+    #[clap(subcommand)]
+    pub command: Option<AreaCommands>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct MetaGetArgs {
+    /// Field to print. Prints all fields without one.
+    pub key: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct MetaSetArgs {
+    /// Fields like "author=Alice" or "tags=smelting,early". An empty value, like "version=",
+    /// removes the field.
+    #[clap(required = true)]
+    pub fields: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct MetaListArgs {
+    /// Directory searched for *.txt blueprints, recursively.
+    pub dir: String,
+    /// Only list blueprints with this tag.
+    #[clap(short, long)]
+    pub tag: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MetaCommands {
+    /// Print metadata fields.
+    Get(MetaGetArgs),
+    /// Set or remove metadata fields.
+    Set(MetaSetArgs),
+    /// List the metadata of the blueprints in a directory. Ignores the input file.
+    List(MetaListArgs),
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct LatitudeArgs {
+    /// Longitude segments at the planet's equator, a multiple of 4. 200 for standard planets.
+    #[clap(short, long, default_value_t = 200)]
+    pub segment: u32,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+pub struct GenBeltArgs {
+    /// Waypoints on the planet-local grid, like "0,0 20,0 20,10".
+    ///
+    /// Belts run along x first, then along y between consecutive waypoints. Negative
+    /// coordinates are allowed; the blueprint is shifted so every belt is inside its area. The
+    /// path may not cross itself.
+    #[clap(required = true, allow_hyphen_values = true)]
+    pub waypoints: Vec<String>,
+    /// Belt tier.
+    #[clap(short = 'T', long, default_value = "ConveyorBeltMKIII")]
+    pub tier: String,
+    /// Icon text of the generated blueprint.
+    #[clap(short = 't', long)]
+    pub icon_text: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GenCommands {
+    /// Generate a linked conveyor belt through a list of waypoints.
+    Belt(GenBeltArgs),
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Dump blueprint to JSON.
     #[cfg(feature = "dump")]
     Dump(DumpArgs),
+    /// Print the JSON Schema of dumps.
+    #[cfg(feature = "dump")]
+    Schema,
     /// Undump blueprint from JSON to blueprint format.
+    ///
+    /// Dumps written by older versions of dspbp are migrated to the current format first.
     #[cfg(feature = "dump")]
     Undump,
+    /// Run an edit script on one or more blueprints.
+    ///
+    /// The script lists operations to run in order, the input files and an output file name
+    /// pattern. See the README for the format.
+    #[cfg(feature = "script")]
+    Apply(ApplyArgs),
     /// Edit blueprint. Accepts more arguments.
     Edit(EditArgs),
     /// Print some blueprint info.
     Info,
     /// Print item names.
-    Items,
+    Items(NamesArgs),
     /// Print recipe names.
-    Recipes,
+    Recipes(NamesArgs),
+    /// Print signal names. Only the number signals 0 to 9 are named; use others as "signal:<number>".
+    Signals(NamesArgs),
+    /// Print technology names. Only the first few are named; use others as "tech:<number>".
+    Techs(NamesArgs),
+    /// Print upgrade families, from the lowest tier up.
+    Families,
+    /// Upgrade buildings within their families, like belts to Mk.III belts.
+    Upgrade(TierArgs),
+    /// Downgrade buildings within their families.
+    Downgrade(TierArgs),
+    /// Check that the blueprint re-serializes to exactly the same binary data.
+    ///
+    /// Reports the first differing byte and the building it belongs to.
+    Verify(VerifyArgs),
+    /// Check for unknown items, wrong building models, broken links and broken areas.
+    Lint,
+    /// Repair a damaged blueprint.
+    ///
+    /// Ignores the hash, recovers as many buildings as decode cleanly and writes a re-signed
+    /// blueprint. Prints a warning for everything that was dropped or fixed.
+    Repair,
+    /// Recompute the hash of a blueprint without parsing it.
+    ///
+    /// Replaces the existing hash, or appends one to an unsigned blueprint.
+    Sign,
+    /// Check the hash of a blueprint without parsing it.
+    CheckHash,
+    /// Replicate a blueprint in a grid of copies.
+    ///
+    /// Buildings are renumbered and connections remapped within each copy.
+    Tile(TileArgs),
+    /// List or edit blueprint areas.
+    Areas(AreasArgs),
+    /// Report which latitudes the blueprint can be pasted at without distortion. Approximate:
+    /// band edges can be off by a latitude segment.
+    Latitude(LatitudeArgs),
+    /// Generate a new blueprint. Ignores the input file.
+    #[clap(subcommand)]
+    Gen(GenCommands),
+    /// Read or edit the metadata block at the end of the description.
+    ///
+    /// The block is a "---" line followed by "key: value" lines, like "author: Alice" and
+    /// "tags: smelting, early".
+    #[clap(subcommand)]
+    Meta(MetaCommands),
 }
@@ -0,0 +1,184 @@
+//! Batch mode: runs a command on every blueprint in a directory.
+//!
+//! Outputs are written to a second directory that mirrors the input directory's layout.
+//! Files are processed in parallel. A file that fails is reported and skipped, and the
+//! command fails once all files are done.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    args::Commands, error::some_error, process, verify::collect_files, writes_output, Processed,
+};
+
+/// Result of running a command on one input file: what it produced and where the output
+/// was written, or the error.
+pub type FileResult = (PathBuf, anyhow::Result<(Processed, Option<PathBuf>)>);
+
+/// File extensions of a command's inputs and outputs.
+fn extensions(command: &Commands) -> (&'static str, &'static str) {
+    match command {
+        #[cfg(feature = "dump")]
+        Commands::Dump(_) => ("txt", "json"),
+        #[cfg(feature = "dump")]
+        Commands::Undump => ("json", "txt"),
+        _ => ("txt", "txt"),
+    }
+}
+
+fn process_file(
+    command: &Commands,
+    compression_level: u32,
+    input_dir: &Path,
+    output_dir: Option<&Path>,
+    file: &Path,
+) -> anyhow::Result<(Processed, Option<PathBuf>)> {
+    let data = std::fs::read(file)?;
+    let mut p = process(command, compression_level, &data)?;
+    let written = match (p.output.take(), output_dir) {
+        (Some(o), Some(dir)) => {
+            let relative = file.strip_prefix(input_dir)?;
+            let out = dir.join(relative).with_extension(extensions(command).1);
+            if let Some(parent) = out.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&out, o)?;
+            Some(out)
+        }
+        _ => None,
+    };
+    Ok((p, written))
+}
+
+/// Runs a command on every input file under `input_dir`, `jobs` files at a time.
+///
+/// Results are in the order of the sorted file names.
+pub fn run_dir(
+    command: &Commands,
+    compression_level: u32,
+    input_dir: &Path,
+    output_dir: Option<&Path>,
+    jobs: Option<usize>,
+) -> anyhow::Result<Vec<FileResult>> {
+    if writes_output(command) {
+        let Some(out) = output_dir else {
+            return Err(some_error(
+                "Batch mode needs an output directory for this command",
+            ));
+        };
+        if out.exists() && std::fs::canonicalize(out)? == std::fs::canonicalize(input_dir)? {
+            return Err(some_error(
+                "The output directory must differ from the input directory",
+            ));
+        }
+    }
+
+    let mut files = vec![];
+    collect_files(input_dir, extensions(command).0, &mut files)?;
+    files.sort();
+
+    let jobs = jobs
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .clamp(1, files.len().max(1));
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, FileResult)> = std::thread::scope(|s| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(file) = files.get(i) else {
+                            return done;
+                        };
+                        let res =
+                            process_file(command, compression_level, input_dir, output_dir, file);
+                        done.push((i, (file.clone(), res)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("batch worker panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    Ok(results.into_iter().map(|(_, r)| r).collect())
+}
+
+fn indent(s: &str) -> String {
+    s.lines().map(|l| format!("  {}\n", l)).collect()
+}
+
+/// Runs a command on a directory and prints a summary. Fails if any file failed.
+pub fn run(
+    command: &Commands,
+    compression_level: u32,
+    input_dir: &Path,
+    output_dir: Option<&Path>,
+    jobs: Option<usize>,
+) -> anyhow::Result<()> {
+    let results = run_dir(command, compression_level, input_dir, output_dir, jobs)?;
+    let mut failed = 0;
+    for (path, res) in results.iter() {
+        match res {
+            Ok((p, written)) => {
+                match (&p.problem, written) {
+                    (Some(problem), _) => {
+                        failed += 1;
+                        println!("FAIL: {}: {}", path.display(), problem)
+                    }
+                    (None, Some(out)) => println!("OK: {} -> {}", path.display(), out.display()),
+                    (None, None) => println!("OK: {}", path.display()),
+                }
+                print!("{}", indent(&p.notes.join("\n")));
+                print!("{}", indent(&p.report));
+            }
+            Err(e) => {
+                failed += 1;
+                println!("FAIL: {}: {:#}", path.display(), e)
+            }
+        }
+    }
+    println!("{} blueprints, {} failed.", results.len(), failed);
+    if failed != 0 {
+        return Err(some_error(format!("{} blueprints failed", failed)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::run_dir;
+    use crate::{args::Commands, blueprint::Blueprint, testutil::get_file};
+
+    #[test]
+    fn mirrors_directory_and_reports_failures() {
+        let base = std::env::temp_dir().join(format!("dspbp-batch-{}", std::process::id()));
+        let (input, output) = (base.join("in"), base.join("out"));
+        std::fs::create_dir_all(input.join("sub")).unwrap();
+        std::fs::write(input.join("a.txt"), get_file("旧版本5飞机.txt")).unwrap();
+        std::fs::write(input.join("sub/b.txt"), get_file("新版本5飞机.txt")).unwrap();
+        std::fs::write(input.join("sub/broken.txt"), "not a blueprint").unwrap();
+
+        let results = run_dir(&Commands::Sign, 6, &input, Some(&output), Some(2)).unwrap();
+        let ok: Vec<PathBuf> = results
+            .iter()
+            .filter_map(|(_, r)| r.as_ref().ok().and_then(|(_, w)| w.clone()))
+            .collect();
+        assert_eq!(ok, vec![output.join("a.txt"), output.join("sub/b.txt")]);
+        assert!(results[2].0.ends_with("sub/broken.txt") && results[2].1.is_err());
+        let signed = std::fs::read_to_string(output.join("sub/b.txt")).unwrap();
+        Blueprint::check_hash(&signed).unwrap();
+
+        assert!(run_dir(&Commands::Sign, 6, &input, None, None).is_err());
+        assert!(run_dir(&Commands::Sign, 6, &input, Some(&input), None).is_err());
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Write as _;
+use std::borrow::Cow;
 use std::io::{Cursor, Read, Write};
 use std::str::FromStr;
 
@@ -8,16 +8,47 @@ use crate::error::{some_error, Error};
 use crate::version::with_game_version;
 use base64::engine::GeneralPurpose;
 use base64::Engine;
-use binrw::{BinReaderExt, BinWrite};
+use binrw::BinWrite;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 #[cfg(feature = "dump")]
+use schemars::JsonSchema;
+#[cfg(feature = "dump")]
 use serde::{Deserialize, Serialize};
 
-use crate::md5::{Algo, MD5Hash, MD5};
+use crate::md5::{self, Algo, MD5Hash, MD5};
+
+/// Options for `Blueprint::new_with_options`.
+#[derive(Clone, Copy, Debug)]
+pub struct ParseOptions {
+    /// Fail if the trailing hash does not match. Otherwise a mismatch is only a warning.
+    pub verify_hash: bool,
+    /// Recover as much as possible from damaged blueprints instead of failing: tolerate a
+    /// missing hash, truncated or padded data, and drop buildings that fail to decode.
+    pub repair: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            verify_hash: true,
+            repair: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options for repairing damaged blueprints.
+    pub fn repair() -> Self {
+        Self {
+            verify_hash: false,
+            repair: true,
+        }
+    }
+}
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
 pub struct Blueprint {
     pub layout: u32,
     pub icons: [u32; 5],
@@ -30,45 +61,156 @@ pub struct Blueprint {
 
 const B64: GeneralPurpose = base64::engine::general_purpose::STANDARD;
 
+/// Names of the CSV header fields, used in error messages.
+const HEADER_FIELDS: [&str; 12] = [
+    "fixed0_1",
+    "layout",
+    "icon0",
+    "icon1",
+    "icon2",
+    "icon3",
+    "icon4",
+    "fixed0_2",
+    "timestamp",
+    "game_version",
+    "icon_text",
+    "desc",
+];
+
 impl Blueprint {
-    fn int<T: FromStr>(data: &str, what: &str) -> Result<T, Error> {
-        str::parse(data).map_err(|_| format!("Failed to parse {}", what).into())
+    fn int<T: FromStr>(data: &str, field: &'static str) -> Result<T, Error> {
+        str::parse(data).map_err(|_| Error::Csv {
+            field,
+            value: data.into(),
+        })
     }
 
-    fn unpack_data(b64data: &str) -> anyhow::Result<(BlueprintData, Vec<u8>)> {
-        let zipped_data = B64
-            .decode(b64data)
-            .map_err(|_| some_error("Failed to base64 decode blueprint"))?;
+    fn unpack_data(
+        b64data: &str,
+        opts: &ParseOptions,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<(BlueprintData, Vec<u8>)> {
+        let zipped_data = if opts.repair {
+            Self::decode_b64_lenient(b64data, warnings)?
+        } else {
+            B64.decode(b64data).map_err(Error::Base64)?
+        };
         let mut d = GzDecoder::new(zipped_data.as_slice());
         let mut data = vec![];
-        d.read_to_end(&mut data)?;
+        if let Err(e) = d.read_to_end(&mut data) {
+            if !opts.repair || data.is_empty() {
+                return Err(Error::Gzip(e).into());
+            }
+            warnings.push(format!(
+                "Compressed data is damaged ({}), recovered {} bytes",
+                e,
+                data.len()
+            ));
+        }
         // Binary V2 format is detected per-building inside BuildingHeader
         // by checking whether the first i32 <= -100. No global flag needed here.
-        let mut c = Cursor::new(data);
-        let out = c.read_le()?;
-        Ok((out, c.into_inner()))
+        if !opts.repair {
+            let out = BlueprintData::read_bytes(&data)?;
+            return Ok((out, data));
+        }
+
+        let partial = BlueprintData::read_partial(&data);
+        let mut out = match (partial.data, partial.error) {
+            (Some(out), None) => {
+                let trailing = data.len() as u64 - partial.consumed;
+                if trailing != 0 {
+                    warnings.push(format!(
+                        "Ignored {} bytes after the last building",
+                        trailing
+                    ));
+                }
+                out
+            }
+            (Some(out), Some(e)) => {
+                warnings.push(format!("{}. Dropped it and all following buildings", e));
+                out
+            }
+            (None, e) => return Err(e.unwrap().into()),
+        };
+        if let Some(old) = out.fix_building_count() {
+            warnings.push(format!(
+                "Recovered {} of {} buildings",
+                out.building_count, old
+            ));
+        }
+        let cleared = out.clear_dangling_links();
+        if cleared != 0 {
+            warnings.push(format!(
+                "Cleared {} connections to missing buildings",
+                cleared
+            ));
+        }
+        Ok((out, data))
+    }
+
+    /// Decodes as much base64 as possible, dropping whitespace, trailing garbage and
+    /// incomplete groups.
+    fn decode_b64_lenient(b64data: &str, warnings: &mut Vec<String>) -> anyhow::Result<Vec<u8>> {
+        let clean: String = b64data.chars().filter(|c| !c.is_whitespace()).collect();
+        let valid = clean
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '='))
+            .unwrap_or(clean.len());
+        if valid != clean.len() {
+            warnings.push(format!(
+                "Ignored {} characters of garbage after the base64 data",
+                clean.len() - valid
+            ));
+        }
+        let mut b64 = &clean[..valid];
+        if let Ok(d) = B64.decode(b64) {
+            return Ok(d);
+        }
+        b64 = b64.trim_end_matches('=');
+        let mut end = b64.len() / 4 * 4;
+        loop {
+            match B64.decode(&b64[..end]) {
+                Ok(d) => {
+                    warnings.push(format!(
+                        "Base64 data is truncated or damaged, decoded {} of {} characters",
+                        end,
+                        b64.len()
+                    ));
+                    return Ok(d);
+                }
+                Err(e) if end < 4 => return Err(Error::Base64(e).into()),
+                Err(_) => end -= 4,
+            }
+        }
     }
 
-    fn hash_str_to_hash(d: &str) -> anyhow::Result<MD5Hash> {
+    fn hash_str_to_hash(d: &str) -> Result<MD5Hash, Error> {
         if d.len() != 32 {
-            return Err(some_error(format!(
-                "Unexpected hash length, expected 32, got {}",
+            return Err(Error::BadHash(format!(
+                "expected 32 hex digits, got {}",
                 d.len()
             )));
         }
-        Ok((0..16)
-            .map(|x| (2 * x..2 * x + 2))
-            .map(|x| &d[x])
-            .map(|x| u8::from_str_radix(x, 16))
-            .collect::<Result<Vec<_>, _>>()?
-            .try_into()
-            .unwrap())
+        md5::parse_hex(d).ok_or_else(|| Error::BadHash(format!("{:?} is not a hex string", d)))
+    }
+
+    fn hash_to_str(hash: &MD5Hash) -> String {
+        md5::hex(hash)
     }
 
     fn hash(data: &str) -> MD5Hash {
         MD5::new(Algo::MD5F).process(data.as_bytes())
     }
 
+    /// Escapes the characters that would split a CSV header field. Setters already URL-encode
+    /// text, but the fields are public and may have been set directly or undumped.
+    fn csv_field(s: &str) -> Cow<'_, str> {
+        if s.contains([',', '"']) {
+            s.replace(',', "%2C").replace('"', "%22").into()
+        } else {
+            s.into()
+        }
+    }
+
     fn pack_data(&self, level: Compression) -> anyhow::Result<String> {
         let mut e = GzEncoder::new(Vec::new(), level);
         let mut ws = Cursor::new(vec![]);
@@ -84,28 +226,86 @@ impl Blueprint {
     }
 
     pub fn new_with_raw_bp(data: &str) -> anyhow::Result<(Self, Vec<u8>)> {
-        let data_and_hash: Vec<&str> = data.rsplitn(2, "\"").collect();
+        Self::parse(data, &ParseOptions::default(), &mut vec![])
+    }
+
+    /// Parses a blueprint with the given options. Returns warnings about anything that was
+    /// ignored or repaired.
+    pub fn new_with_options(
+        data: &str,
+        opts: &ParseOptions,
+    ) -> anyhow::Result<(Self, Vec<String>)> {
+        let mut warnings = vec![];
+        let (me, _) = Self::parse(data, opts, &mut warnings)?;
+        Ok((me, warnings))
+    }
+
+    /// Splits a blueprint string into data and hash. In repair mode, tolerates a missing hash
+    /// and garbage after it.
+    fn split_hash<'a>(
+        data: &'a str,
+        opts: &ParseOptions,
+        warnings: &mut Vec<String>,
+    ) -> Result<(&'a str, Option<MD5Hash>), Error> {
+        let data_and_hash: Vec<&str> = data.rsplitn(2, '"').collect();
         if data_and_hash.len() != 2 {
-            return Err(some_error("Did not find hash delimiter"));
+            return Err(Error::BadHash("did not find hash delimiter".into()));
+        }
+        let [hash, rest]: [&str; 2] = data_and_hash.try_into().unwrap();
+        let hash = hash.trim();
+        if !opts.repair {
+            return Ok((rest.trim(), Some(Self::hash_str_to_hash(hash)?)));
+        }
+
+        let hex_len = hash
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(hash.len());
+        if hex_len < 32 {
+            warnings.push("No hash found after the blueprint data".into());
+            return Ok((data.trim(), None));
+        }
+        if hash.len() > 32 {
+            warnings.push(format!(
+                "Ignored {} characters after the hash",
+                hash.len() - 32
+            ));
+        }
+        Ok((rest.trim(), Some(Self::hash_str_to_hash(&hash[..32])?)))
+    }
+
+    fn parse(
+        data: &str,
+        opts: &ParseOptions,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<(Self, Vec<u8>)> {
+        const PREFIX: &str = "BLUEPRINT:";
+        let mut data = data.trim();
+        if opts.repair {
+            if let Some(start) = data.find(PREFIX).filter(|s| *s != 0) {
+                warnings.push(format!("Ignored {} characters before the blueprint", start));
+                data = &data[start..];
+            }
         }
-        let [mut hash, mut data]: [&str; 2] = data_and_hash.try_into().unwrap();
-        hash = hash.trim();
-        data = data.trim();
+        let (mut data, hash) = Self::split_hash(data, opts, warnings)?;
 
         // NOTICE: we hash the blueprint without the trailing quote!
-        let hash = Self::hash_str_to_hash(hash)?;
         let our_hash = Self::hash(data);
-        if hash != our_hash {
-            return Err(some_error(format!(
-                "Blueprint hash does not match calculated hash: {:x?} != {:x?}",
-                hash, our_hash
-            )));
+        match hash {
+            Some(hash) if hash != our_hash => {
+                let e = Error::HashMismatch {
+                    expected: Self::hash_to_str(&hash),
+                    actual: Self::hash_to_str(&our_hash),
+                };
+                if opts.verify_hash {
+                    return Err(e.into());
+                }
+                warnings.push(e.to_string());
+            }
+            _ => (),
         }
 
-        const PREFIX: &str = "BLUEPRINT:";
         if data.len() < PREFIX.len() || &data[0..PREFIX.len()] != PREFIX {
-            let ml = std::cmp::min(PREFIX.len(), data.len());
-            return Err(some_error(format!("Unexpected prefix: {}", &data[0..ml])));
+            return Err(Error::BadPrefix(data.chars().take(PREFIX.len()).collect()).into());
         }
         data = &data[PREFIX.len()..];
 
@@ -120,11 +320,12 @@ impl Blueprint {
         let b64data = &data[quote_pos + 1..];
 
         let fields: Vec<&str> = csv_part.split(',').collect();
-        if fields.len() < 12 {
-            return Err(some_error(format!(
-                "Expected at least 12 CSV elements, got {}",
-                fields.len()
-            )));
+        if fields.len() < HEADER_FIELDS.len() {
+            return Err(Error::Csv {
+                field: HEADER_FIELDS[fields.len()],
+                value: "".into(),
+            }
+            .into());
         }
 
         let [_fixed0_1, layout]: [&str; 2] = fields[0..2].try_into().unwrap();
@@ -142,7 +343,8 @@ impl Blueprint {
         let timestamp = Self::int(timestamp, "timestamp")?;
         // fixed0_1 / fixed0_2 are ignored: V10 uses 1 instead of 0.
 
-        let (data, raw_bp) = with_game_version(game_version, || Self::unpack_data(b64data))?;
+        let (data, raw_bp) =
+            with_game_version(game_version, || Self::unpack_data(b64data, opts, warnings))?;
 
         Ok((
             Self {
@@ -157,11 +359,54 @@ impl Blueprint {
             raw_bp,
         ))
     }
+    /// Splits off the trailing hash of a blueprint string, if it has one.
+    fn strip_hash(data: &str) -> (&str, Option<&str>) {
+        let data = data.trim();
+        match data.rsplit_once('"') {
+            // Unsigned blueprints end with the payload, which is never 32 hex digits.
+            Some((rest, hash)) if rest.contains('"') && md5::parse_hex(hash.trim()).is_some() => {
+                (rest.trim_end(), Some(hash.trim()))
+            }
+            _ => (data, None),
+        }
+    }
+
+    /// Signs a blueprint string, replacing its hash if it has one. Does not parse the blueprint.
+    pub fn sign(data: &str) -> anyhow::Result<String> {
+        let (data, _) = Self::strip_hash(data);
+        if !data.starts_with("BLUEPRINT:") {
+            return Err(Error::BadPrefix(data.chars().take(10).collect()).into());
+        }
+        Ok(format!(
+            "{}\"{}",
+            data,
+            Self::hash_to_str(&Self::hash(data))
+        ))
+    }
+
+    /// Checks the hash of a blueprint string without parsing the blueprint.
+    ///
+    /// Returns an error if the blueprint has no hash, or a `HashMismatch` error if it's wrong.
+    pub fn check_hash(data: &str) -> Result<(), Error> {
+        let (data, hash) = match Self::strip_hash(data) {
+            (data, Some(hash)) => (data, Self::hash_str_to_hash(hash)?),
+            (_, None) => return Err(Error::BadHash("blueprint has no hash".into())),
+        };
+        let our_hash = Self::hash(data);
+        if hash != our_hash {
+            return Err(Error::HashMismatch {
+                expected: Self::hash_to_str(&hash),
+                actual: Self::hash_to_str(&our_hash),
+            });
+        }
+        Ok(())
+    }
+
     pub fn into_bp_string(&self, level: u32) -> anyhow::Result<String> {
         let icons = self.icons.map(|x| x.to_string()).join(",");
         // Build the header + payload inside with_game_version so is_v10() is
         // set correctly for both pack_data (binary) and the CSV format changes.
-        let mut out = with_game_version(&self.game_version, || -> anyhow::Result<String> {
+        let out = with_game_version(&self.game_version, || -> anyhow::Result<String> {
             let b64data = self.pack_data(Compression::new(level))?;
             // V10: fixed0_1 = 1, and 2 extra empty fields after desc before '"'
             let (fixed0_1, extra) = if crate::version::is_v10() {
@@ -172,31 +417,48 @@ impl Blueprint {
             Ok(format!(
                 "BLUEPRINT:{},{},{},0,{},{},{},{}{}\"{}",
                 fixed0_1, self.layout, icons, self.timestamp,
-                self.game_version, self.icon_text, self.desc, extra, b64data,
+                Self::csv_field(&self.game_version),
+                Self::csv_field(&self.icon_text),
+                Self::csv_field(&self.desc),
+                extra,
+                b64data,
             ))
         })?;
-        let hash = Self::hash(&out);
-        write!(&mut out, "\"").unwrap();
-        for b in hash {
-            write!(&mut out, "{:02X}", b).unwrap();
-        }
-        Ok(out)
+        Self::sign(&out)
     }
 
+    /// Reads a dump of any format version. Errors name the offending field, like
+    /// `data.buildings[42].header.recipe_id`.
     #[cfg(feature = "dump")]
     pub fn new_from_json(json: &str) -> anyhow::Result<Self> {
-        Ok(serde_json::from_str(json)?)
+        crate::dump::from_json(json)
     }
 
     #[cfg(feature = "dump")]
     pub fn dump_json(&self) -> anyhow::Result<Vec<u8>> {
-        Ok(serde_json::to_vec(self)?)
+        crate::dump::to_json(self)
+    }
+
+    /// Like `dump_json`, with human readable names in `locale` instead of numeric IDs.
+    #[cfg(feature = "dump")]
+    pub(crate) fn dump_json_in(&self, locale: crate::locale::Locale) -> anyhow::Result<Vec<u8>> {
+        crate::locale::with_serialization_locale(locale, || self.dump_json())
+    }
+
+    /// JSON Schema of the output of `dump_json`.
+    #[cfg(feature = "dump")]
+    pub fn dump_schema() -> anyhow::Result<String> {
+        crate::dump::schema()
     }
 
     pub fn get_description(&self) -> anyhow::Result<String> {
         Ok(urlencoding::decode(&self.desc)?.into_owned())
     }
 
+    pub fn set_description(&mut self, text: &str) {
+        self.desc = urlencoding::encode(text).into_owned();
+    }
+
     pub fn set_icon_text(&mut self, text: &str) {
         self.icon_text = urlencoding::encode(text).into_owned();
     }
@@ -204,6 +466,21 @@ impl Blueprint {
     pub fn get_icon_text(&self) -> anyhow::Result<String> {
         Ok(urlencoding::decode(&self.icon_text)?.into_owned())
     }
+
+    /// The time the blueprint was saved, as "YYYY-MM-DD HH:MM:SS".
+    pub fn get_timestamp(&self) -> String {
+        crate::timestamp::format(self.timestamp)
+    }
+
+    /// Sets the save time from "now", a date like "2024-01-31 12:30" or a raw tick count.
+    pub fn set_timestamp(&mut self, date: &str) -> anyhow::Result<()> {
+        self.timestamp = crate::timestamp::parse(date)?;
+        Ok(())
+    }
+
+    pub fn set_layout(&mut self, layout: u32) {
+        self.layout = layout;
+    }
 }
 
 impl Visit for Blueprint {
@@ -214,7 +491,8 @@ impl Visit for Blueprint {
 
 #[cfg(test)]
 mod test {
-    use super::Blueprint;
+    use super::{Blueprint, ParseOptions};
+    use crate::{data::blueprint::BlueprintData, error::Error, testutil::get_file};
 
     /// Parse → serialize → re-parse; both parse steps must succeed.
     fn round_trip(raw: &str) -> Blueprint {
@@ -307,4 +585,189 @@ mod test {
             bp2.game_version
         );
     }
+
+    fn example() -> String {
+        let f = get_file("旧版本5飞机.txt");
+        std::str::from_utf8(&f).unwrap().trim().to_owned()
+    }
+
+    fn error_of(raw: &str) -> Error {
+        let e = Blueprint::new(raw).err().expect("parse should fail");
+        e.downcast::<Error>().expect("expected a typed error")
+    }
+
+    #[test]
+    fn typed_hash_mismatch() {
+        let mut raw = example();
+        let last = raw.pop().unwrap();
+        raw.push(if last == '0' { '1' } else { '0' });
+        assert!(matches!(error_of(&raw), Error::HashMismatch { .. }));
+    }
+
+    #[test]
+    fn typed_bad_prefix() {
+        let data = "BLUEPRUNT:0,1,0,0,0,0,0,0,0,0.10.28.21011,,\"AAAA";
+        let raw = format!(
+            "{}\"{}",
+            data,
+            Blueprint::hash_to_str(&Blueprint::hash(data))
+        );
+        assert!(matches!(error_of(&raw), Error::BadPrefix(p) if p == "BLUEPRUNT:"));
+    }
+
+    #[test]
+    fn typed_csv_field() {
+        let data = "BLUEPRINT:0,x,0,0,0,0,0,0,0,0.10.28.21011,,\"AAAA";
+        let raw = format!(
+            "{}\"{}",
+            data,
+            Blueprint::hash_to_str(&Blueprint::hash(data))
+        );
+        assert!(matches!(
+            error_of(&raw),
+            Error::Csv {
+                field: "layout",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn decode_error_names_building() {
+        let (bp, mut raw) = Blueprint::new_with_raw_bp(&example()).unwrap();
+        raw.truncate(raw.len() - 3);
+        let e = BlueprintData::read_bytes(&raw).err().unwrap();
+        match e {
+            Error::Decode {
+                building: Some(b), ..
+            } => {
+                assert_eq!(b.position, bp.data.buildings.len() - 1);
+                assert!(b.index.is_some());
+            }
+            e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn repair_truncated_blueprint() {
+        let raw = example();
+        let full = Blueprint::new(&raw).unwrap().data.buildings.len();
+        let truncated = &raw[..raw.len() * 2 / 3];
+        assert!(Blueprint::new(truncated).is_err());
+
+        let (bp, warnings) =
+            Blueprint::new_with_options(truncated, &ParseOptions::repair()).unwrap();
+        assert!(!warnings.is_empty());
+        let recovered = bp.data.buildings.len();
+        assert!(recovered > 0 && recovered < full);
+        assert_eq!(bp.data.building_count as usize, recovered);
+
+        let fixed = Blueprint::new(&bp.into_bp_string(6).unwrap()).unwrap();
+        assert_eq!(fixed.data.buildings.len(), recovered);
+    }
+
+    #[test]
+    fn repair_garbage_around_blueprint() {
+        let raw = format!("Check this out: {}0 thanks!", example());
+        assert!(Blueprint::new(&raw).is_err());
+        let (bp, warnings) = Blueprint::new_with_options(&raw, &ParseOptions::repair()).unwrap();
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert_eq!(bp.data.buildings.len(), bp.data.building_count as usize);
+    }
+
+    #[test]
+    fn skip_hash_verification() {
+        let mut raw = example();
+        let last = raw.pop().unwrap();
+        raw.push(if last == '0' { '1' } else { '0' });
+        let opts = ParseOptions {
+            verify_hash: false,
+            ..Default::default()
+        };
+        let (_, warnings) = Blueprint::new_with_options(&raw, &opts).unwrap();
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn sign_and_check_hash() {
+        let raw = example();
+        assert!(Blueprint::check_hash(&raw).is_ok());
+        assert_eq!(Blueprint::sign(&raw).unwrap(), raw);
+
+        let (unsigned, _) = raw.rsplit_once('"').unwrap();
+        assert!(matches!(
+            Blueprint::check_hash(unsigned),
+            Err(Error::BadHash(_))
+        ));
+        assert_eq!(Blueprint::sign(unsigned).unwrap(), raw);
+
+        let tampered = raw.replacen("BLUEPRINT:0,51", "BLUEPRINT:0,52", 1);
+        assert!(matches!(
+            Blueprint::check_hash(&tampered),
+            Err(Error::HashMismatch { .. })
+        ));
+        assert!(Blueprint::new(&Blueprint::sign(&tampered).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn metadata_with_commas_and_quotes_round_trips() {
+        let mut bp = Blueprint::new(&example()).unwrap();
+        let text = "Smelting, \"v2\"\n50% faster";
+        bp.set_description(text);
+        bp.set_icon_text(text);
+        bp.set_timestamp("2024-01-31 12:30").unwrap();
+        bp.set_layout(10);
+        let bp = Blueprint::new(&bp.into_bp_string(6).unwrap()).unwrap();
+        assert_eq!(bp.get_description().unwrap(), text);
+        assert_eq!(bp.get_icon_text().unwrap(), text);
+        assert_eq!(bp.get_timestamp(), "2024-01-31 12:30:00");
+        assert_eq!(bp.layout, 10);
+
+        // Fields set directly are escaped too.
+        let mut bp = bp;
+        bp.desc = "a,b\"c".into();
+        let bp = Blueprint::new(&bp.into_bp_string(6).unwrap()).unwrap();
+        assert_eq!(bp.get_description().unwrap(), "a,b\"c");
+    }
+
+    #[cfg(feature = "dump")]
+    #[test]
+    fn human_readable_dump_undumps() {
+        use crate::locale::Locale;
+
+        let bp = Blueprint::new(&example()).unwrap();
+        let json = String::from_utf8(bp.dump_json_in(Locale::cn).unwrap()).unwrap();
+        assert!(json.contains("\"model_index\":\"极速传送带\""));
+        let undumped = Blueprint::new_from_json(&json).unwrap();
+        assert_eq!(
+            undumped.into_bp_string(6).unwrap(),
+            bp.into_bp_string(6).unwrap()
+        );
+    }
+
+    #[cfg(feature = "dump")]
+    #[test]
+    fn published_schema_is_current() {
+        let published = include_str!("../schema/dump.schema.json");
+        assert_eq!(
+            published.trim(),
+            Blueprint::dump_schema().unwrap(),
+            "Regenerate with `dspbp schema > schema/dump.schema.json`"
+        );
+    }
+
+    #[cfg(feature = "dump")]
+    #[test]
+    fn undump_errors_name_the_field() {
+        let bp = Blueprint::new(&example()).unwrap();
+        let mut json: serde_json::Value = serde_json::from_slice(&bp.dump_json().unwrap()).unwrap();
+        json["data"]["buildings"][42]["header"]["recipe_id"] = serde_json::json!(true);
+        let e = Blueprint::new_from_json(&json.to_string()).err().unwrap();
+        assert!(
+            e.to_string()
+                .contains("data.buildings[42].header.recipe_id"),
+            "{}",
+            e
+        );
+    }
 }
@@ -0,0 +1,383 @@
+use crate::{
+    blueprint::Blueprint,
+    data::{
+        area::Area,
+        assembler::{Assembler, ProliferatorMode},
+        belt::Belt,
+        blueprint::{BlueprintData, Header},
+        building::{Building, BuildingHeader, BuildingParam},
+        enums::{BuildingClass, DSPItem, DSPRecipe},
+    },
+    error::some_error,
+};
+
+/// Game version written into built blueprints unless another one is set.
+pub const DEFAULT_GAME_VERSION: &str = "0.10.28.21011";
+
+/// Longitude segments at the equator of a standard size planet.
+const EQUATOR_SEGMENTS: u16 = 200;
+
+/// Yaw in degrees of the direction from `a` to `b`. 0 is north (+y), 90 is east (+x).
+pub(crate) fn yaw_between(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let yaw = (b.0 - a.0).atan2(b.1 - a.1).to_degrees();
+    if yaw < 0.0 {
+        yaw + 360.0
+    } else {
+        yaw
+    }
+}
+
+/// Expands waypoints into every grid cell of the path between them.
+///
+/// Each leg is laid along x first, then along y, so diagonal legs turn one corner. Repeated
+/// points are dropped. Paths that cross or double back onto a cell they already passed are
+/// rejected, since belts cannot share a cell.
+pub fn rasterize_path(waypoints: &[(i32, i32)]) -> anyhow::Result<Vec<(f32, f32)>> {
+    let mut cells: Vec<(i32, i32)> = vec![];
+    let mut push = |c: (i32, i32)| {
+        if cells.last() != Some(&c) {
+            cells.push(c);
+        }
+    };
+    for (i, &(x, y)) in waypoints.iter().enumerate() {
+        let Some(&(px, py)) = i.checked_sub(1).map(|i| &waypoints[i]) else {
+            push((x, y));
+            continue;
+        };
+        let mut cx = px;
+        while cx != x {
+            cx += (x - cx).signum();
+            push((cx, py));
+        }
+        let mut cy = py;
+        while cy != y {
+            cy += (y - cy).signum();
+            push((x, cy));
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some((x, y)) = cells.iter().find(|c| !seen.insert(**c)) {
+        return Err(some_error(format!(
+            "The belt path passes through ({}, {}) more than once",
+            x, y
+        )));
+    }
+    Ok(cells
+        .into_iter()
+        .map(|(x, y)| (x as f32, y as f32))
+        .collect())
+}
+
+/// Builds a blueprint from scratch.
+///
+/// Positions are planet-local grid coordinates of a single area, with (0, 0) at its bottom
+/// left corner. The area is sized to fit all buildings when the blueprint is built, and
+/// buildings are shifted right or up if any of them are at negative coordinates.
+pub struct BlueprintBuilder {
+    game_version: String,
+    buildings: Vec<Building>,
+}
+
+impl Default for BlueprintBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlueprintBuilder {
+    pub fn new() -> Self {
+        Self {
+            game_version: DEFAULT_GAME_VERSION.to_owned(),
+            buildings: vec![],
+        }
+    }
+
+    /// Sets the game version. This also selects the binary format that gets written.
+    pub fn with_game_version(mut self, version: &str) -> Self {
+        self.game_version = version.to_owned();
+        self
+    }
+
+    fn push(&mut self, mut header: BuildingHeader, param: Vec<u32>) -> i32 {
+        let index = self.buildings.len() as i32;
+        header.index = index;
+        header.parameter_count = param.len() as u16;
+        self.buildings.push(Building {
+            header,
+            param: BuildingParam::Unknown(param),
+        });
+        index
+    }
+
+    fn header_mut(&mut self, index: i32) -> anyhow::Result<&mut BuildingHeader> {
+        self.buildings
+            .get_mut(index as usize)
+            .map(|b| &mut b.header)
+            .ok_or_else(|| some_error(format!("No building with index {}", index)))
+    }
+
+    /// Places a building with no recipe and no parameters, e.g. a power pole.
+    pub fn building(&mut self, item: DSPItem, x: f32, y: f32, yaw: f32) -> anyhow::Result<i32> {
+        let header = BuildingHeader::new(0, item, x, y, yaw)?;
+        Ok(self.push(header, vec![]))
+    }
+
+    /// Places an assembler, smelter or chemical plant with a recipe.
+    pub fn assembler(
+        &mut self,
+        item: DSPItem,
+        x: f32,
+        y: f32,
+        yaw: f32,
+        recipe: DSPRecipe,
+    ) -> anyhow::Result<i32> {
+        match BuildingClass::from(item) {
+            BuildingClass::Assembler | BuildingClass::Smelter | BuildingClass::ChemPlant => (),
+            _ => {
+                return Err(some_error(format!(
+                    "{} is not an assembler, smelter or chemical plant",
+                    item.as_ref()
+                )))
+            }
+        }
+        let mut header = BuildingHeader::new(0, item, x, y, yaw)?;
+        header.recipe_id = recipe.into();
+        // The only parameter is the proliferator mode.
+        let index = self.push(header, vec![0]);
+        self.buildings[index as usize].param =
+            BuildingParam::Assembler(Assembler::new(ProliferatorMode::ExtraProducts));
+        Ok(index)
+    }
+
+    /// Places a belt through the given points, linking each belt to the next one.
+    ///
+    /// Points should be one grid cell apart. Returns the indices of the placed belts, in
+    /// order.
+    pub fn belt(&mut self, tier: DSPItem, points: &[(f32, f32)]) -> anyhow::Result<Vec<i32>> {
+        if !tier.is_belt() {
+            return Err(some_error(format!("{} is not a belt", tier.as_ref())));
+        }
+        let mut indices = vec![];
+        let mut yaw = 0.0;
+        for (i, p) in points.iter().enumerate() {
+            if let Some(next) = points.get(i + 1) {
+                yaw = yaw_between(*p, *next);
+            }
+            let mut header = BuildingHeader::new(0, tier, p.0, p.1, yaw)?;
+            header.input_to_slot = 1;
+            let index = self.push(header, vec![]);
+            self.buildings[index as usize].param = BuildingParam::Belt(None::<Belt>);
+            indices.push(index);
+        }
+        for pair in indices.windows(2) {
+            self.link_belt(pair[0], pair[1])?;
+        }
+        Ok(indices)
+    }
+
+    /// Places a belt along a path through the given waypoints. See `rasterize_path`.
+    pub fn belt_path(
+        &mut self,
+        tier: DSPItem,
+        waypoints: &[(i32, i32)],
+    ) -> anyhow::Result<Vec<i32>> {
+        if waypoints.is_empty() {
+            return Err(some_error("Belt path needs at least one waypoint"));
+        }
+        self.belt(tier, &rasterize_path(waypoints)?)
+    }
+
+    /// Makes the belt `from` feed into the belt `to`.
+    pub fn link_belt(&mut self, from: i32, to: i32) -> anyhow::Result<()> {
+        self.header_mut(to)?;
+        let h = self.header_mut(from)?;
+        h.output_object_index = to as u32;
+        h.output_to_slot = 1;
+        Ok(())
+    }
+
+    /// Places a sorter that takes items from building `from` at `start` and puts them into
+    /// building `to` at `end`.
+    pub fn sorter(
+        &mut self,
+        tier: DSPItem,
+        from: i32,
+        to: i32,
+        start: (f32, f32),
+        end: (f32, f32),
+    ) -> anyhow::Result<i32> {
+        if BuildingClass::from(tier) != BuildingClass::Sorter {
+            return Err(some_error(format!("{} is not a sorter", tier.as_ref())));
+        }
+        self.header_mut(from)?;
+        self.header_mut(to)?;
+        let yaw = yaw_between(start, end);
+        let mut header = BuildingHeader::new(0, tier, start.0, start.1, yaw)?;
+        header.local_offset_x2 = end.0;
+        header.local_offset_y2 = end.1;
+        header.input_object_index = from as u32;
+        header.output_object_index = to as u32;
+        header.output_to_slot = -1;
+        header.input_from_slot = -1;
+        header.input_to_slot = 1;
+        // The only parameter is the sorter length in grid cells.
+        let length = (end.0 - start.0)
+            .hypot(end.1 - start.1)
+            .round()
+            .clamp(1.0, 3.0);
+        Ok(self.push(header, vec![length as u32]))
+    }
+
+    pub fn build(mut self) -> Blueprint {
+        let (mut min_x, mut min_y) = (0f32, 0f32);
+        for b in self.buildings.iter() {
+            let h = &b.header;
+            min_x = min_x.min(h.local_offset_x).min(h.local_offset_x2);
+            min_y = min_y.min(h.local_offset_y).min(h.local_offset_y2);
+        }
+        for b in self.buildings.iter_mut() {
+            let h = &mut b.header;
+            h.local_offset_x -= min_x;
+            h.local_offset_x2 -= min_x;
+            h.local_offset_y -= min_y;
+            h.local_offset_y2 -= min_y;
+        }
+        let (mut width, mut height) = (1u16, 1u16);
+        for b in self.buildings.iter() {
+            let h = &b.header;
+            for (x, y) in [
+                (h.local_offset_x, h.local_offset_y),
+                (h.local_offset_x2, h.local_offset_y2),
+            ] {
+                width = width.max(x.max(0.0).ceil() as u16 + 1);
+                height = height.max(y.max(0.0).ceil() as u16 + 1);
+            }
+        }
+        let header = Header {
+            version: 1,
+            cursor_offset_x: width as u32 / 2,
+            cursor_offset_y: height as u32 / 2,
+            cursor_target_area: 0,
+            dragbox_size_x: width as u32,
+            dragbox_size_y: height as u32,
+            primary_area_index: 0,
+            area_count: 1,
+        };
+        let area = Area {
+            index: 0,
+            parent_index: -1,
+            tropic_anchor: 0,
+            area_segments: EQUATOR_SEGMENTS,
+            anchor_local_offset_x: 0,
+            anchor_local_offset_y: 0,
+            width,
+            height,
+        };
+        Blueprint {
+            layout: 10,
+            icons: [0; 5],
+            timestamp: 0,
+            game_version: self.game_version,
+            icon_text: String::new(),
+            desc: String::new(),
+            data: BlueprintData {
+                header,
+                areas: vec![area],
+                building_count: self.buildings.len() as u32,
+                buildings: self.buildings,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{rasterize_path, BlueprintBuilder};
+    use crate::{
+        blueprint::Blueprint,
+        data::{
+            building::{BuildingParam, NO_OBJECT},
+            enums::{DSPItem, DSPRecipe},
+        },
+        verify::verify,
+    };
+
+    #[test]
+    fn build_smelter_with_belts() {
+        let mut b = BlueprintBuilder::new();
+        let smelter = b
+            .assembler(DSPItem::ArcSmelter, 5.0, 5.0, 0.0, DSPRecipe::IronIngot)
+            .unwrap();
+        let belt = b
+            .belt(
+                DSPItem::ConveyorBeltMKI,
+                &[(0.0, 2.0), (1.0, 2.0), (2.0, 2.0)],
+            )
+            .unwrap();
+        let sorter = b
+            .sorter(DSPItem::SorterMKI, belt[1], smelter, (1.0, 2.5), (1.0, 3.5))
+            .unwrap();
+        assert!(b
+            .assembler(DSPItem::ConveyorBeltMKI, 0.0, 0.0, 0.0, DSPRecipe::Gear)
+            .is_err());
+
+        let s = b.build().into_bp_string(6).unwrap();
+        assert!(verify(&s).unwrap().is_none());
+        let bp = Blueprint::new(&s).unwrap();
+        let hs: Vec<_> = bp.data.buildings.iter().map(|b| &b.header).collect();
+        assert_eq!(hs.len(), 5);
+        assert_eq!(hs[belt[0] as usize].output_object_index, belt[1] as u32);
+        assert_eq!(hs[belt[0] as usize].yaw, 90.0);
+        assert_eq!(hs[belt[2] as usize].output_object_index, NO_OBJECT);
+        assert_eq!(hs[sorter as usize].input_object_index, belt[1] as u32);
+        assert_eq!(hs[sorter as usize].output_object_index, smelter as u32);
+        assert!(
+            matches!(&bp.data.buildings[sorter as usize].param, BuildingParam::Unknown(p) if p == &[1])
+        );
+        assert_eq!(bp.data.areas[0].width, 6);
+    }
+
+    #[test]
+    fn belt_path_turns_corners() {
+        assert_eq!(
+            rasterize_path(&[(0, 0), (2, 0), (2, 0), (1, -1)])
+                .unwrap_err()
+                .to_string(),
+            "The belt path passes through (1, 0) more than once"
+        );
+        assert_eq!(
+            rasterize_path(&[(0, 0), (2, 0), (2, 0), (3, -1)]).unwrap(),
+            vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0), (3.0, -1.0)]
+        );
+
+        let mut b = BlueprintBuilder::new();
+        let belt = b
+            .belt_path(DSPItem::ConveyorBeltMKII, &[(0, 0), (0, 3), (2, 3)])
+            .unwrap();
+        assert_eq!(belt.len(), 6);
+        let bp = b.build();
+        let yaws: Vec<_> = bp.data.buildings.iter().map(|b| b.header.yaw).collect();
+        assert_eq!(yaws, vec![0.0, 0.0, 0.0, 90.0, 90.0, 90.0]);
+        for pair in bp.data.buildings.windows(2) {
+            assert_eq!(
+                pair[0].header.output_object_index,
+                pair[1].header.index as u32
+            );
+        }
+
+        // Negative waypoints are shifted into the area.
+        let mut b = BlueprintBuilder::new();
+        b.belt_path(DSPItem::ConveyorBeltMKI, &[(0, 0), (-5, 0)])
+            .unwrap();
+        let bp = b.build();
+        let xs: Vec<_> = bp
+            .data
+            .buildings
+            .iter()
+            .map(|b| b.header.local_offset_x)
+            .collect();
+        assert_eq!(xs, vec![5.0, 4.0, 3.0, 2.0, 1.0, 0.0]);
+        assert_eq!(bp.data.areas[0].width, 6);
+        assert!(crate::edit::lint::lint(&bp.data).is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+//! Planet grid coordinates.
+//!
+//! DSP lays a grid over every planet. Both hemispheres are cut into `segment / 4` latitude
+//! segments, and each latitude segment into a number of longitude segments that shrinks
+//! towards the poles. Every segment is 5×5 grid cells. Rows of latitude segments that share a
+//! longitude segment count form a band, and a blueprint area remembers the band it was
+//! copied in (`area_segments`) and its row within that band (`tropic_anchor`).
+//!
+//! Pasted outside its band, an area is stretched or squeezed along the longitude, so
+//! buildings no longer line up with the grid.
+//!
+//! The game computes `ceil(cos(latitude) * segment)` and snaps it up to a fixed table of
+//! counts. `SEGMENT_TABLE` is a reconstruction of that table, so band edges reported here
+//! can be off by a latitude segment.
+
+use std::{fmt::Display, ops::Range};
+
+use crate::{
+    data::{area::Area, blueprint::BlueprintData},
+    error::some_error,
+};
+
+/// Grid cells along each side of a planet segment.
+pub const CELLS_PER_SEGMENT: u32 = 5;
+
+/// Longitude segments at the equator of a standard, radius 200 planet.
+pub const DEFAULT_SEGMENT: u32 = 200;
+
+/// Longitude segment counts that bands snap up to. Larger counts round up to hundreds.
+const SEGMENT_TABLE: [u32; 13] = [4, 8, 12, 16, 20, 32, 40, 60, 80, 100, 120, 160, 200];
+
+/// Position on the planet grid, in grid cells.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridPos {
+    /// Rows north of the equator. Negative in the southern hemisphere.
+    pub row: f64,
+    /// Cells east of longitude 0, counted at this row's cell size.
+    pub col: f64,
+}
+
+/// Position on the planet, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPos {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Grid layout of one planet size.
+#[derive(Debug, Clone, Copy)]
+pub struct PlanetGrid {
+    /// Longitude segments at the equator.
+    pub segment: u32,
+}
+
+impl Default for PlanetGrid {
+    fn default() -> Self {
+        Self {
+            segment: DEFAULT_SEGMENT,
+        }
+    }
+}
+
+impl PlanetGrid {
+    /// Fails unless `segment` is a positive multiple of 4, as on every planet in the game.
+    // `u32::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn new(segment: u32) -> anyhow::Result<Self> {
+        if segment < 4 || segment % 4 != 0 {
+            return Err(some_error(format!(
+                "Planet segment count must be a positive multiple of 4, got {}",
+                segment
+            )));
+        }
+        Ok(Self { segment })
+    }
+
+    /// Latitude segments between the equator and a pole.
+    pub fn latitude_segments(&self) -> u32 {
+        self.segment / 4
+    }
+
+    /// Grid rows between the equator and a pole.
+    pub fn rows(&self) -> u32 {
+        self.latitude_segments() * CELLS_PER_SEGMENT
+    }
+
+    /// Longitude segments around the planet in a latitude segment, counted from the equator.
+    pub fn longitude_segments(&self, latitude_segment: u32) -> u32 {
+        let lat = latitude_segment as f64 / self.latitude_segments() as f64 * 90.0;
+        let raw = (lat.to_radians().cos().abs() * self.segment as f64).ceil() as u32;
+        match SEGMENT_TABLE.iter().find(|s| **s >= raw) {
+            Some(s) => *s,
+            None => raw.div_ceil(100) * 100,
+        }
+    }
+
+    /// Longitude segments around the planet at a grid row.
+    pub fn segments_at_row(&self, row: i32) -> u32 {
+        let lat_segment = row.unsigned_abs() / CELLS_PER_SEGMENT;
+        self.longitude_segments(lat_segment.min(self.latitude_segments() - 1))
+    }
+
+    /// Northern hemisphere rows of every band, from the equator up, with their longitude
+    /// segment count. Approximate, see `SEGMENT_TABLE`.
+    pub fn bands(&self) -> Vec<(u32, Range<u32>)> {
+        let mut bands: Vec<(u32, Range<u32>)> = vec![];
+        for lat_segment in 0..self.latitude_segments() {
+            let segments = self.longitude_segments(lat_segment);
+            let rows = lat_segment * CELLS_PER_SEGMENT..(lat_segment + 1) * CELLS_PER_SEGMENT;
+            match bands.last_mut() {
+                Some((s, r)) if *s == segments => r.end = rows.end,
+                _ => bands.push((segments, rows)),
+            }
+        }
+        bands
+    }
+
+    /// Northern hemisphere rows of the band with the given longitude segment count.
+    pub fn band(&self, segments: u32) -> Option<Range<u32>> {
+        self.bands()
+            .into_iter()
+            .find(|(s, _)| *s == segments)
+            .map(|(_, r)| r)
+    }
+
+    fn cell_degrees(&self, segments: u32) -> f64 {
+        360.0 / (segments * CELLS_PER_SEGMENT) as f64
+    }
+
+    pub fn to_geo(&self, p: GridPos) -> GeoPos {
+        let segments = self.segments_at_row(p.row.floor() as i32);
+        GeoPos {
+            latitude: p.row / self.rows() as f64 * 90.0,
+            longitude: p.col * self.cell_degrees(segments),
+        }
+    }
+
+    pub fn to_grid(&self, g: GeoPos) -> GridPos {
+        let row = g.latitude / 90.0 * self.rows() as f64;
+        let segments = self.segments_at_row(row.floor() as i32);
+        GridPos {
+            row,
+            col: g.longitude / self.cell_degrees(segments),
+        }
+    }
+
+    /// Planet position of a point in blueprint area coordinates, with the area pasted in
+    /// its own band in the northern hemisphere and its left edge at column `anchor_col`.
+    ///
+    /// Returns `None` if no band on this planet has the area's segment count.
+    pub fn local_to_grid(&self, area: &Area, x: f32, y: f32, anchor_col: f64) -> Option<GridPos> {
+        let band = self.band(area.area_segments as u32)?;
+        let row = band.start as f64 + area.tropic_anchor as f64 + y as f64;
+        let longitude = (anchor_col + x as f64) * self.cell_degrees(area.area_segments as u32);
+        let segments = self.segments_at_row(row.floor() as i32);
+        Some(GridPos {
+            row,
+            col: longitude / self.cell_degrees(segments),
+        })
+    }
+
+    /// Inverse of `local_to_grid`.
+    pub fn grid_to_local(&self, area: &Area, p: GridPos, anchor_col: f64) -> Option<(f32, f32)> {
+        let band = self.band(area.area_segments as u32)?;
+        let segments = self.segments_at_row(p.row.floor() as i32);
+        let longitude = p.col * self.cell_degrees(segments);
+        let x = longitude / self.cell_degrees(area.area_segments as u32) - anchor_col;
+        let y = p.row - band.start as f64 - area.tropic_anchor as f64;
+        Some((x as f32, y as f32))
+    }
+}
+
+/// How one blueprint area fits the planet grid.
+#[derive(Debug)]
+pub struct AreaLatitudeReport {
+    pub area_index: i8,
+    pub area_segments: u16,
+    /// Planet rows covered by buildings when pasted in the area's own band. `None` if the
+    /// planet has no band with the area's segment count.
+    pub rows: Option<Range<i32>>,
+    /// Buildings reach rows outside the area's own band, so some of them get distorted even
+    /// when pasted where the blueprint was made.
+    pub distorts: bool,
+    /// Highest latitude in degrees that the area can be pasted at without crossing a band
+    /// edge, or `None` if it does not fit in any band.
+    pub max_latitude: Option<f64>,
+    /// The area fits in every band, up to the pole.
+    pub polar_safe: bool,
+}
+
+/// How a blueprint fits the planet grid. See `latitude_report`.
+///
+/// Band edges come from the reconstructed `SEGMENT_TABLE`, so rows, latitudes and verdicts
+/// are approximate and can be off by a latitude segment.
+#[derive(Debug)]
+pub struct LatitudeReport {
+    pub areas: Vec<AreaLatitudeReport>,
+}
+
+impl LatitudeReport {
+    pub fn polar_safe(&self) -> bool {
+        self.areas.iter().all(|a| a.polar_safe)
+    }
+
+    pub fn distorts(&self) -> bool {
+        self.areas.iter().any(|a| a.distorts)
+    }
+}
+
+impl Display for LatitudeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for a in self.areas.iter() {
+            write!(
+                f,
+                "Area {}: {} longitude segments",
+                a.area_index, a.area_segments
+            )?;
+            match &a.rows {
+                Some(r) => writeln!(f, ", rows {}..{}", r.start, r.end)?,
+                None => writeln!(f, ", not a segment count of this planet")?,
+            }
+            if a.distorts {
+                writeln!(f, "  Crosses a band edge, will distort.")?;
+            }
+            match (a.polar_safe, a.max_latitude) {
+                (true, _) => writeln!(f, "  Polar safe.")?,
+                (false, Some(l)) => writeln!(f, "  Fits without distortion up to {:.1}°.", l)?,
+                (false, None) => writeln!(f, "  Does not fit in any band.")?,
+            }
+        }
+        writeln!(
+            f,
+            "Polar safe: {}. Distorts: {}.",
+            if self.polar_safe() { "yes" } else { "no" },
+            if self.distorts() { "yes" } else { "no" }
+        )?;
+        writeln!(f, "{}", APPROXIMATE)
+    }
+}
+
+/// Printed under every latitude report.
+pub const APPROXIMATE: &str =
+    "Approximate: band edges are reconstructed, not the game's table, and can be off by a latitude segment (5 rows).";
+
+/// Checks where each area of a blueprint can be pasted on a planet without distortion. The
+/// result is approximate, see `LatitudeReport`.
+pub fn latitude_report(data: &BlueprintData, grid: &PlanetGrid) -> LatitudeReport {
+    let bands = grid.bands();
+    let areas = data
+        .areas
+        .iter()
+        .map(|area| {
+            let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+            for b in data
+                .buildings
+                .iter()
+                .filter(|b| b.header.area_index == area.index)
+            {
+                for y in [b.header.local_offset_y, b.header.local_offset_y2] {
+                    lo = lo.min(y);
+                    hi = hi.max(y);
+                }
+            }
+            if lo > hi {
+                (lo, hi) = (0.0, area.height.saturating_sub(1) as f32);
+            }
+            let (lo, hi) = (lo.round() as i32, hi.round() as i32);
+            let height = (hi - lo + 1) as u32;
+
+            let rows = grid.band(area.area_segments as u32).map(|band| {
+                let base = band.start as i32 + area.tropic_anchor as i32;
+                base + lo..base + hi + 1
+            });
+            let distorts = match &rows {
+                Some(r) => r
+                    .clone()
+                    .any(|row| grid.segments_at_row(row) != area.area_segments as u32),
+                None => true,
+            };
+            let fits: Vec<&(u32, Range<u32>)> = bands
+                .iter()
+                .filter(|(s, r)| {
+                    r.len() as u32 >= height && s * CELLS_PER_SEGMENT >= area.width as u32
+                })
+                .collect();
+            let max_latitude = fits
+                .last()
+                .map(|(_, r)| r.end as f64 / grid.rows() as f64 * 90.0);
+            AreaLatitudeReport {
+                area_index: area.index,
+                area_segments: area.area_segments,
+                rows,
+                distorts,
+                max_latitude,
+                polar_safe: fits.len() == bands.len(),
+            }
+        })
+        .collect();
+    LatitudeReport { areas }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{latitude_report, GridPos, PlanetGrid, APPROXIMATE};
+    use crate::{blueprint::Blueprint, testutil::get_file};
+
+    #[test]
+    fn grid_round_trips() {
+        let grid = PlanetGrid::default();
+        assert_eq!(grid.rows(), 250);
+        assert_eq!(grid.longitude_segments(0), 200);
+        assert!(grid.longitude_segments(49) < 20);
+        let bands = grid.bands();
+        assert_eq!(bands[0].0, 200);
+        assert_eq!(bands.last().unwrap().1.end, 250);
+        assert_eq!(PlanetGrid::new(4).unwrap().bands().len(), 1);
+        for bad in [0, 2, 199] {
+            assert!(PlanetGrid::new(bad).is_err(), "{}", bad);
+        }
+
+        let p = GridPos {
+            row: 100.0,
+            col: 37.0,
+        };
+        let g = grid.to_geo(p);
+        assert!((g.latitude - 36.0).abs() < 1e-9);
+        let back = grid.to_grid(g);
+        assert!((back.row - p.row).abs() < 1e-9 && (back.col - p.col).abs() < 1e-9);
+    }
+
+    #[test]
+    fn example_fits_its_band() {
+        let f = get_file("旧版本5飞机.txt");
+        let bp = Blueprint::new(std::str::from_utf8(&f).unwrap().trim()).unwrap();
+        let grid = PlanetGrid::default();
+        let report = latitude_report(&bp.data, &grid);
+        let a = &report.areas[0];
+        assert_eq!(a.area_segments, 120);
+        let rows = a.rows.clone().unwrap();
+        assert_eq!(rows.len(), 25);
+        assert!(!report.polar_safe());
+        // Band rows come from the reconstructed segment table and are only approximate.
+        assert!(report.to_string().ends_with(&format!("{}\n", APPROXIMATE)));
+
+        let area = &bp.data.areas[0];
+        let p = grid.local_to_grid(area, 10.0, 3.0, 5.0).unwrap();
+        assert_eq!(p.row as i32, rows.start + 3);
+        let (x, y) = grid.grid_to_local(area, p, 5.0).unwrap();
+        assert!((x - 10.0).abs() < 1e-3 && (y - 3.0).abs() < 1e-3);
+    }
+}
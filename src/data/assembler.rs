@@ -0,0 +1,68 @@
+use binrw::{BinRead, BinWrite};
+#[cfg(feature = "dump")]
+use schemars::JsonSchema;
+#[cfg(feature = "dump")]
+use serde::{Deserialize, Serialize};
+
+use super::visit::Visit;
+
+/// How a production building uses proliferated inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProliferatorMode {
+    ExtraProducts,
+    Speedup,
+}
+
+impl std::str::FromStr for ProliferatorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace(['-', ' '], "_").as_str() {
+            "extra" | "extra_products" => Ok(Self::ExtraProducts),
+            "speed" | "speedup" | "production_speedup" => Ok(Self::Speedup),
+            _ => Err(crate::error::some_error(format!(
+                "Unknown proliferator mode \"{}\". Expected \"extra_products\" or \"speedup\".",
+                s
+            ))),
+        }
+    }
+}
+
+/// Parameters of assemblers, smelters, chemical plants, refineries and particle colliders.
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
+#[br(import(param_count: usize))]
+#[br(pre_assert(param_count >= 1))]
+pub struct Assembler {
+    /// Non-zero to use proliferated inputs for production speedup instead of extra products.
+    /// Recipes that cannot make extra products always speed up.
+    #[br(little)]
+    pub force_acc_mode: u32,
+    /// Parameters after the mode, if a newer game version writes any. Kept as is.
+    #[br(little, count = param_count - 1)]
+    pub rest: Vec<u32>,
+}
+
+impl Assembler {
+    pub fn new(mode: ProliferatorMode) -> Self {
+        Self {
+            force_acc_mode: (mode == ProliferatorMode::Speedup) as u32,
+            rest: vec![],
+        }
+    }
+
+    pub fn mode(&self) -> ProliferatorMode {
+        match self.force_acc_mode {
+            0 => ProliferatorMode::ExtraProducts,
+            _ => ProliferatorMode::Speedup,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: ProliferatorMode) {
+        self.force_acc_mode = (mode == ProliferatorMode::Speedup) as u32;
+    }
+}
+
+impl Visit for Assembler {
+    fn visit<T: super::visit::Visitor + ?Sized>(&mut self, _visitor: &mut T) {}
+}
@@ -1,11 +1,13 @@
 use binrw::{BinRead, BinWrite};
 #[cfg(feature = "dump")]
+use schemars::JsonSchema;
+#[cfg(feature = "dump")]
 use serde::{Deserialize, Serialize};
 
 use super::{traits::IconId, visit::Visit};
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 #[br(import(param_count: usize))]
 // Relaxed: require at least 2 params; newer game may add more (extras are ignored)
 // #[br(pre_assert(param_count == 2))]
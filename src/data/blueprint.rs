@@ -1,33 +1,41 @@
-use binrw::{BinRead, BinWrite};
+use std::{collections::HashSet, io::Cursor};
+
+use binrw::{BinRead, BinReaderExt, BinWrite};
+#[cfg(feature = "dump")]
+use schemars::JsonSchema;
 #[cfg(feature = "dump")]
 use serde::{Deserialize, Serialize};
 
-use crate::data::{area::Area, building::Building};
+use crate::data::{
+    area::Area,
+    building::{Building, BuildingHeader, NO_OBJECT},
+};
+use crate::error::{BuildingContext, Error};
 
 use super::visit::{Visit, Visitor};
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 pub struct Header {
     #[br(little)]
-    version: u32,
+    pub version: u32,
     #[br(little)]
-    cursor_offset_x: u32,
+    pub cursor_offset_x: u32,
     #[br(little)]
-    cursor_offset_y: u32,
+    pub cursor_offset_y: u32,
     #[br(little)]
-    cursor_target_area: u32,
+    pub cursor_target_area: u32,
     #[br(little)]
-    dragbox_size_x: u32,
+    pub dragbox_size_x: u32,
     #[br(little)]
-    dragbox_size_y: u32,
+    pub dragbox_size_y: u32,
     #[br(little)]
-    primary_area_index: u32,
-    area_count: u8,
+    pub primary_area_index: u32,
+    pub area_count: u8,
 }
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 pub struct BlueprintData {
     // #[br(assert(header.version == 1))]
     // Version check relaxed: newer game versions may use version > 1
@@ -40,6 +48,137 @@ pub struct BlueprintData {
     pub buildings: Vec<Building>,
 }
 
+fn binrw_offset(e: &binrw::Error) -> Option<u64> {
+    match e.root_cause() {
+        binrw::Error::BadMagic { pos, .. }
+        | binrw::Error::AssertFail { pos, .. }
+        | binrw::Error::Custom { pos, .. }
+        | binrw::Error::NoVariantMatch { pos }
+        | binrw::Error::EnumErrors { pos, .. } => Some(*pos),
+        _ => None,
+    }
+}
+
+fn decode_error(e: binrw::Error, start: u64, building: Option<BuildingContext>) -> Error {
+    Error::Decode {
+        offset: binrw_offset(&e).unwrap_or(start),
+        building,
+        message: e.root_cause().to_string(),
+    }
+}
+
+/// Result of `BlueprintData::read_partial`.
+pub struct PartialRead {
+    /// Data read so far, if the header and areas could be read. `building_count` is left as
+    /// stored, not as read.
+    pub data: Option<BlueprintData>,
+    /// Error that stopped reading, if any.
+    pub error: Option<Error>,
+    /// Number of bytes consumed by the data that was read.
+    pub consumed: u64,
+}
+
+impl BlueprintData {
+    /// Reads blueprint data one building at a time, stopping at the first one that fails to
+    /// decode.
+    pub fn read_partial(bytes: &[u8]) -> PartialRead {
+        let mut c = Cursor::new(bytes);
+        let mut read_head = || -> Result<Self, binrw::Error> {
+            let header: Header = c.read_le()?;
+            let areas = (0..header.area_count)
+                .map(|_| c.read_le())
+                .collect::<Result<Vec<Area>, _>>()?;
+            let building_count = c.read_le()?;
+            Ok(Self {
+                header,
+                areas,
+                building_count,
+                buildings: vec![],
+            })
+        };
+        let mut data = match read_head() {
+            Ok(d) => d,
+            Err(e) => {
+                return PartialRead {
+                    data: None,
+                    error: Some(decode_error(e, 0, None)),
+                    consumed: 0,
+                }
+            }
+        };
+
+        for position in 0..data.building_count as usize {
+            let start = c.position();
+            match c.read_le::<Building>() {
+                Ok(b) => data.buildings.push(b),
+                Err(e) => {
+                    c.set_position(start);
+                    let header = c.read_le::<BuildingHeader>().ok();
+                    let building = BuildingContext {
+                        position,
+                        index: header.as_ref().map(|h| h.index),
+                        item: header.as_ref().map(|h| h.item_id),
+                    };
+                    return PartialRead {
+                        data: Some(data),
+                        error: Some(decode_error(e, start, Some(building))),
+                        consumed: start,
+                    };
+                }
+            }
+        }
+        PartialRead {
+            data: Some(data),
+            error: None,
+            consumed: c.position(),
+        }
+    }
+
+    /// Reads blueprint data, reporting decode failures with the offending building.
+    pub fn read_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        match Cursor::new(bytes).read_le() {
+            Ok(data) => Ok(data),
+            Err(e) => match Self::read_partial(bytes).error {
+                Some(err) => Err(err),
+                // Should not happen, but don't lose the original error if it does.
+                None => Err(decode_error(e, 0, None)),
+            },
+        }
+    }
+
+    /// Sets `building_count` to the number of buildings. Returns the old count if it changed.
+    pub fn fix_building_count(&mut self) -> Option<u32> {
+        let count = self.buildings.len() as u32;
+        if self.building_count == count {
+            return None;
+        }
+        Some(std::mem::replace(&mut self.building_count, count))
+    }
+
+    /// Clears input/output links to buildings that are not in the blueprint. Returns the
+    /// number of links cleared.
+    pub fn clear_dangling_links(&mut self) -> usize {
+        let indices: HashSet<u32> = self
+            .buildings
+            .iter()
+            .map(|b| b.header.index as u32)
+            .collect();
+        let mut cleared = 0;
+        for b in self.buildings.iter_mut() {
+            for link in [
+                &mut b.header.output_object_index,
+                &mut b.header.input_object_index,
+            ] {
+                if *link != NO_OBJECT && !indices.contains(link) {
+                    *link = NO_OBJECT;
+                    cleared += 1;
+                }
+            }
+        }
+        cleared
+    }
+}
+
 impl Visit for BlueprintData {
     fn visit<T: Visitor + ?Sized>(&mut self, visitor: &mut T) {
         for b in self.buildings.iter_mut() {
@@ -1,22 +1,28 @@
 use binrw::{BinRead, BinWrite};
 use num_enum::TryFromPrimitiveError;
 #[cfg(feature = "dump")]
+use schemars::JsonSchema;
+#[cfg(feature = "dump")]
 use serde::{Deserialize, Serialize};
 
 use super::{
+    assembler::Assembler,
     belt::Belt,
-    enums::DSPItem,
+    enums::{BPModel, DSPItem},
     station::Station,
     traits::{BPModelId, ItemId, RecipeId},
     visit::{Visit, Visitor},
 };
 
+/// Value of `output_object_index`/`input_object_index` for unconnected buildings.
+pub const NO_OBJECT: u32 = u32::MAX;
+
 fn b_is(i: ItemId<u16>, f: fn(&DSPItem) -> bool) -> bool {
     i.try_into().as_ref().map(f).unwrap_or(false)
 }
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 #[br(import { param_count: usize, building: ItemId<u16> })]
 // #[br(pre_assert(param_count <= 32768))] // Arbitrary upper bound to prevent OOM; removed because newer game versions may exceed this
 // param_count upper bound removed: newer game versions may exceed 32768
@@ -32,6 +38,8 @@ pub enum BuildingParam {
         #[br(args(param_count))]
         Option<Belt>,
     ),
+    #[br(pre_assert(b_is(building, DSPItem::has_proliferator_mode)))]
+    Assembler(#[br(args(param_count))] Assembler),
     Unknown(
         #[br(count = param_count)]
         #[br(little)]
@@ -39,8 +47,8 @@ pub enum BuildingParam {
     ),
 }
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 #[br(little)]
 pub struct BuildingHeader {
     // DSP >= 0.10.30.22239 ("V10") prepends a magic_version i32 before the
@@ -80,14 +88,59 @@ pub struct BuildingHeader {
     pub parameter_count: u16,
 }
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+impl BuildingHeader {
+    /// Header for an unconnected building with no recipe and no parameters.
+    pub fn new(index: i32, item: DSPItem, x: f32, y: f32, yaw: f32) -> anyhow::Result<Self> {
+        Ok(Self {
+            magic_version: 0,
+            index,
+            area_index: 0,
+            local_offset_x: x,
+            local_offset_y: y,
+            local_offset_z: 0.0,
+            local_offset_x2: x,
+            local_offset_y2: y,
+            local_offset_z2: 0.0,
+            yaw,
+            yaw2: yaw,
+            tilt: 0.0,
+            item_id: item.into(),
+            model_index: BPModel::from_building(item)?.into(),
+            output_object_index: NO_OBJECT,
+            input_object_index: NO_OBJECT,
+            output_to_slot: 0,
+            input_from_slot: 0,
+            output_from_slot: 0,
+            input_to_slot: 0,
+            output_offset: 0,
+            input_offset: 0,
+            recipe_id: RecipeId(0),
+            filter_id: ItemId(0),
+            parameter_count: 0,
+        })
+    }
+}
+
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 pub struct Building {
     pub header: BuildingHeader,
     #[br(args { param_count: header.parameter_count as usize, building: header.item_id })]
     pub param: BuildingParam,
 }
 
+impl BuildingParam {
+    /// Number of u32 words the parameters take up when written.
+    pub fn param_count(&self) -> usize {
+        match self {
+            BuildingParam::Station(s) => s.param_count(),
+            BuildingParam::Belt(b) => b.as_ref().map_or(0, |_| 2),
+            BuildingParam::Assembler(a) => 1 + a.rest.len(),
+            BuildingParam::Unknown(v) => v.len(),
+        }
+    }
+}
+
 impl Building {
     pub fn kind(&self) -> Result<DSPItem, TryFromPrimitiveError<DSPItem>> {
         DSPItem::try_from(self.header.item_id)
@@ -99,6 +152,7 @@ impl Visit for Building {
         match &mut self.param {
             BuildingParam::Station(s) => visitor.visit_station(s),
             BuildingParam::Belt(Some(b)) => visitor.visit_belt(b),
+            BuildingParam::Assembler(a) => visitor.visit_assembler(a),
             _ => (),
         }
     }
@@ -2,6 +2,8 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::fmt::Debug;
 use strum::{AsRefStr, EnumIter, EnumString};
 
+use super::families;
+
 #[cfg_attr(feature = "python", pyo3::pyclass)]
 #[derive(
     TryFromPrimitive,
@@ -214,6 +216,24 @@ impl DSPItem {
     pub fn is_interstellar_station(&self) -> bool {
         [Self::InterstellarLogisticsStation].contains(self)
     }
+
+    /// Production buildings with a proliferator mode setting.
+    pub fn has_proliferator_mode(&self) -> bool {
+        [
+            Self::AssemblingMachineMkI,
+            Self::AssemblingMachineMkII,
+            Self::AssemblingMachineMkIII,
+            Self::RecomposingAssembler,
+            Self::ArcSmelter,
+            Self::PlaneSmelter,
+            Self::NegentropySmelter,
+            Self::ChemicalPlant,
+            Self::QuantumChemicalPlant,
+            Self::OilRefinery,
+            Self::MiniatureParticleCollider,
+        ]
+        .contains(self)
+    }
 }
 
 #[cfg(feature = "python")]
@@ -416,6 +436,61 @@ impl DSPRecipe {
     }
 }
 
+/// Named signal icons, only the number signals 0 to 9. Other signals stay numbers.
+/// Generated from data/en/signals.txt by update_data.py.
+#[derive(
+    TryFromPrimitive,
+    IntoPrimitive,
+    EnumString,
+    EnumIter,
+    AsRefStr,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    Debug,
+)]
+#[repr(u16)]
+pub enum DSPSignal {
+    //DSPSignal enum start
+    Number0 = 600,
+    Number1 = 601,
+    Number2 = 602,
+    Number3 = 603,
+    Number4 = 604,
+    Number5 = 605,
+    Number6 = 606,
+    Number7 = 607,
+    Number8 = 608,
+    Number9 = 609,
+    //DSPSignal enum end
+}
+
+/// Named technologies, only the first few. Other technologies stay numbers. Generated from
+/// data/en/techs.txt by update_data.py.
+#[derive(
+    TryFromPrimitive,
+    IntoPrimitive,
+    EnumString,
+    EnumIter,
+    AsRefStr,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    Debug,
+)]
+#[repr(u16)]
+pub enum DSPTech {
+    //DSPTech enum start
+    DysonSphereProgram = 1,
+    Electromagnetism = 1001,
+    ElectromagneticMatrix = 1002,
+    //DSPTech enum end
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
 pub enum DSPIcon {
     Signal(u32),
@@ -459,9 +534,32 @@ impl From<DSPIcon> for u32 {
     }
 }
 
+/// Writes icons the way `try_from_user_string` reads them: item names as is, everything else
+/// with a prefix, like "recipe:Gear" or "signal:Number0". Signals and technologies without a
+/// name are written as numbers, like "signal:405".
+impl std::fmt::Display for DSPIcon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DSPIcon::Signal(v) => match DSPSignal::try_from(*v) {
+                Ok(s) => write!(f, "signal:{}", s.as_ref()),
+                Err(_) => write!(f, "signal:{}", v),
+            },
+            DSPIcon::Item(v) => write!(f, "{}", v.as_ref()),
+            DSPIcon::Recipe(v) => write!(f, "recipe:{}", v.as_ref()),
+            DSPIcon::Tech(v) => match DSPTech::try_from(*v) {
+                Ok(t) => write!(f, "tech:{}", t.as_ref()),
+                Err(_) => write!(f, "tech:{}", v),
+            },
+            DSPIcon::Unknown(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Building models. Generated from data/models.txt by update_data.py.
 #[derive(TryFromPrimitive, IntoPrimitive, PartialEq, Eq, Clone, Copy, Hash, Debug)]
 #[repr(u16)]
 pub enum BPModel {
+    //BPModel enum start
     ConveyorBeltMKI = 35,
     ConveyorBeltMKII = 36,
     ConveyorBeltMKIII = 37,
@@ -469,23 +567,28 @@ pub enum BPModel {
     SorterMKII = 42,
     SorterMKIII = 43,
     SorterMKIV = 483,
+    InterstellarLogisticsStation = 50,
+    TeslaTower = 44,
+    SatelliteSubstation = 68,
+    ArcSmelter = 62,
     AssemblingMachineMkI = 65,
     AssemblingMachineMkII = 66,
     AssemblingMachineMkIII = 67,
-    ArcSmelter = 62,
+    ChemicalPlant = 80,
+    MiniatureParticleCollider = 69,
+    SprayCoater = 120,
     PlaneSmelter = 194,
-    // DLC / newer buildings (model IDs from community research)
-    NegentropySmelter = 344,
-    RecomposingAssembler = 373,
     QuantumChemicalPlant = 376,
-    ChemicalPlant = 80,
-    MatrixLab = 69,
+    RecomposingAssembler = 373,
+    NegentropySmelter = 344,
     SelfevolutionLab = 418,
+    //BPModel enum end
 }
 
 impl BPModel {
     pub fn from_building(i: DSPItem) -> anyhow::Result<Self> {
         let o = match i {
+            //BPModel from_building start
             DSPItem::ConveyorBeltMKI => Self::ConveyorBeltMKI,
             DSPItem::ConveyorBeltMKII => Self::ConveyorBeltMKII,
             DSPItem::ConveyorBeltMKIII => Self::ConveyorBeltMKIII,
@@ -493,21 +596,56 @@ impl BPModel {
             DSPItem::SorterMKII => Self::SorterMKII,
             DSPItem::SorterMKIII => Self::SorterMKIII,
             DSPItem::SorterMKIV => Self::SorterMKIV,
+            DSPItem::InterstellarLogisticsStation => Self::InterstellarLogisticsStation,
+            DSPItem::TeslaTower => Self::TeslaTower,
+            DSPItem::SatelliteSubstation => Self::SatelliteSubstation,
+            DSPItem::ArcSmelter => Self::ArcSmelter,
             DSPItem::AssemblingMachineMkI => Self::AssemblingMachineMkI,
             DSPItem::AssemblingMachineMkII => Self::AssemblingMachineMkII,
             DSPItem::AssemblingMachineMkIII => Self::AssemblingMachineMkIII,
-            DSPItem::RecomposingAssembler => Self::RecomposingAssembler,
-            DSPItem::ArcSmelter => Self::ArcSmelter,
-            DSPItem::PlaneSmelter => Self::PlaneSmelter,
-            DSPItem::NegentropySmelter => Self::NegentropySmelter,
             DSPItem::ChemicalPlant => Self::ChemicalPlant,
+            DSPItem::MiniatureParticleCollider => Self::MiniatureParticleCollider,
+            DSPItem::SprayCoater => Self::SprayCoater,
+            DSPItem::PlaneSmelter => Self::PlaneSmelter,
             DSPItem::QuantumChemicalPlant => Self::QuantumChemicalPlant,
-            DSPItem::MatrixLab => Self::MatrixLab,
+            DSPItem::RecomposingAssembler => Self::RecomposingAssembler,
+            DSPItem::NegentropySmelter => Self::NegentropySmelter,
             DSPItem::SelfevolutionLab => Self::SelfevolutionLab,
+            //BPModel from_building end
             _ => anyhow::bail!("Building {:?} has no BP model", i),
         };
         Ok(o)
     }
+
+    /// The building that uses this model.
+    pub fn building(&self) -> DSPItem {
+        match self {
+            //BPModel building start
+            Self::ConveyorBeltMKI => DSPItem::ConveyorBeltMKI,
+            Self::ConveyorBeltMKII => DSPItem::ConveyorBeltMKII,
+            Self::ConveyorBeltMKIII => DSPItem::ConveyorBeltMKIII,
+            Self::SorterMKI => DSPItem::SorterMKI,
+            Self::SorterMKII => DSPItem::SorterMKII,
+            Self::SorterMKIII => DSPItem::SorterMKIII,
+            Self::SorterMKIV => DSPItem::SorterMKIV,
+            Self::InterstellarLogisticsStation => DSPItem::InterstellarLogisticsStation,
+            Self::TeslaTower => DSPItem::TeslaTower,
+            Self::SatelliteSubstation => DSPItem::SatelliteSubstation,
+            Self::ArcSmelter => DSPItem::ArcSmelter,
+            Self::AssemblingMachineMkI => DSPItem::AssemblingMachineMkI,
+            Self::AssemblingMachineMkII => DSPItem::AssemblingMachineMkII,
+            Self::AssemblingMachineMkIII => DSPItem::AssemblingMachineMkIII,
+            Self::ChemicalPlant => DSPItem::ChemicalPlant,
+            Self::MiniatureParticleCollider => DSPItem::MiniatureParticleCollider,
+            Self::SprayCoater => DSPItem::SprayCoater,
+            Self::PlaneSmelter => DSPItem::PlaneSmelter,
+            Self::QuantumChemicalPlant => DSPItem::QuantumChemicalPlant,
+            Self::RecomposingAssembler => DSPItem::RecomposingAssembler,
+            Self::NegentropySmelter => DSPItem::NegentropySmelter,
+            Self::SelfevolutionLab => DSPItem::SelfevolutionLab,
+            //BPModel building end
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -522,8 +660,12 @@ pub enum BuildingClass {
 }
 
 impl BuildingClass {
+    /// Whether `i` can be replaced with `o`, i.e. both are in the same upgrade family.
     pub fn replacement_is_valid(i: DSPItem, o: DSPItem) -> bool {
-        return Self::from(i) == Self::from(o) && Self::from(i) != Self::Other;
+        match (families::family_of(i), families::family_of(o)) {
+            (Some(a), Some(b)) => a.id == b.id,
+            _ => false,
+        }
     }
 }
 
@@ -0,0 +1,115 @@
+//! Upgrade families: buildings that can replace each other in place.
+//!
+//! Buildings are only replaced within a family. Adding a family here makes it available to
+//! building replacement, `upgrade_all`/`downgrade_all` and the web UI once at least two of
+//! its tiers have a known model. Until then it is listed but left alone.
+
+use super::enums::{BPModel, DSPItem};
+
+pub struct Family {
+    pub id: &'static str,
+    /// Name shown in the web UI.
+    pub label: &'static str,
+    /// Members from the lowest tier up.
+    pub tiers: &'static [DSPItem],
+}
+
+impl Family {
+    /// Position of a building in this family, 0 for the lowest tier.
+    pub fn tier_of(&self, i: DSPItem) -> Option<usize> {
+        self.tiers.iter().position(|t| *t == i)
+    }
+
+    /// Whether buildings can move between tiers: at least two tiers have a known model.
+    pub fn is_available(&self) -> bool {
+        self.tiers
+            .iter()
+            .filter(|t| BPModel::from_building(**t).is_ok())
+            .count()
+            >= 2
+    }
+}
+
+pub static FAMILIES: &[Family] = &[
+    Family {
+        id: "belt",
+        label: "传送带 (Conveyor Belt)",
+        tiers: &[
+            DSPItem::ConveyorBeltMKI,
+            DSPItem::ConveyorBeltMKII,
+            DSPItem::ConveyorBeltMKIII,
+        ],
+    },
+    Family {
+        id: "sorter",
+        label: "分拣器 (Sorter)",
+        tiers: &[
+            DSPItem::SorterMKI,
+            DSPItem::SorterMKII,
+            DSPItem::SorterMKIII,
+            DSPItem::SorterMKIV,
+        ],
+    },
+    Family {
+        id: "assembler",
+        label: "制造台 (Assembler)",
+        tiers: &[
+            DSPItem::AssemblingMachineMkI,
+            DSPItem::AssemblingMachineMkII,
+            DSPItem::AssemblingMachineMkIII,
+            DSPItem::RecomposingAssembler,
+        ],
+    },
+    Family {
+        id: "smelter",
+        label: "熔炉 (Smelter)",
+        tiers: &[
+            DSPItem::ArcSmelter,
+            DSPItem::PlaneSmelter,
+            DSPItem::NegentropySmelter,
+        ],
+    },
+    Family {
+        id: "chemplant",
+        label: "化工厂 (Chemical Plant)",
+        tiers: &[DSPItem::ChemicalPlant, DSPItem::QuantumChemicalPlant],
+    },
+    Family {
+        id: "lab",
+        label: "研究站 (Matrix Lab)",
+        tiers: &[DSPItem::MatrixLab, DSPItem::SelfevolutionLab],
+    },
+    Family {
+        id: "miner",
+        label: "采矿机 (Mining Machine)",
+        tiers: &[DSPItem::MiningMachine, DSPItem::AdvancedMiningMachine],
+    },
+    Family {
+        id: "depot",
+        label: "储物仓 (Depot)",
+        tiers: &[DSPItem::DepotMKI, DSPItem::DepotMKII],
+    },
+    Family {
+        id: "power",
+        label: "电力塔 (Power Tower)",
+        tiers: &[DSPItem::TeslaTower, DSPItem::WirelessPowerTower],
+    },
+    Family {
+        id: "turret",
+        label: "防御塔 (Turret)",
+        tiers: &[DSPItem::GaussTurret, DSPItem::LaserTurret],
+    },
+];
+
+pub fn family(id: &str) -> Option<&'static Family> {
+    FAMILIES.iter().find(|f| f.id == id)
+}
+
+/// Families buildings can currently move within.
+pub fn available_families() -> impl Iterator<Item = &'static Family> {
+    FAMILIES.iter().filter(|f| f.is_available())
+}
+
+pub fn family_of(i: DSPItem) -> Option<&'static Family> {
+    FAMILIES.iter().find(|f| f.tiers.contains(&i))
+}
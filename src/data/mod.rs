@@ -1,8 +1,10 @@
 pub mod area;
+pub mod assembler;
 pub mod belt;
 pub mod blueprint;
 pub mod building;
 pub mod enums;
+pub mod families;
 pub mod station;
 pub mod traits;
 pub mod visit;
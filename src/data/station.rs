@@ -2,6 +2,8 @@ use std::f64::consts::PI;
 
 use binrw::{BinRead, BinWrite};
 #[cfg(feature = "dump")]
+use schemars::JsonSchema;
+#[cfg(feature = "dump")]
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -9,8 +11,8 @@ use super::{
     visit::{Visit, Visitor},
 };
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 pub struct StationHeader {
     #[br(little)]
     pub work_energy_per_tick: u32, // In watts. 1M is 60MW/s.
@@ -41,8 +43,8 @@ impl StationHeader {
     }
 }
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 pub struct StationSlots {
     #[br(little)]
     pub direction: u32,
@@ -58,8 +60,8 @@ impl Visit for StationSlots {
     fn visit<T: Visitor + ?Sized>(&mut self, _visitor: &mut T) {}
 }
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 pub struct StationStorage {
     #[br(little)]
     pub item_id: ItemId<u32>,
@@ -79,8 +81,8 @@ impl Visit for StationStorage {
     fn visit<T: Visitor + ?Sized>(&mut self, _visitor: &mut T) {}
 }
 
-#[cfg_attr(feature = "dump", derive(Serialize, Deserialize))]
-#[derive(BinRead, BinWrite)]
+#[cfg_attr(feature = "dump", derive(Serialize, Deserialize, JsonSchema))]
+#[derive(BinRead, BinWrite, Clone)]
 // Relaxed: require at least 329 u32s (5×storage + unknown1 + 12×slots + unknown2 + header)
 // to handle newer game versions with param_count > 2048
 // #[br(pre_assert(param_count == 2048))]
@@ -93,13 +95,17 @@ pub struct Station {
 
     // ignore last 2 if not interstellar
     #[br(count = 5)]
+    #[cfg_attr(feature = "dump", schemars(length(equal = 5)))]
     pub storage: Vec<StationStorage>,
     #[br(count = 192 - 30)]
+    #[cfg_attr(feature = "dump", schemars(length(equal = 162)))]
     pub unknown1: Vec<u32>,
 
     #[br(count = 12)]
+    #[cfg_attr(feature = "dump", schemars(length(equal = 12)))]
     pub slots: Vec<StationSlots>, // Counter-clockwise, from rightmost north.
     #[br(count = 320 - 192 - 48)]
+    #[cfg_attr(feature = "dump", schemars(length(equal = 80)))]
     pub unknown2: Vec<u32>,
 
     pub header: StationHeader, // 320
@@ -110,6 +116,35 @@ pub struct Station {
 }
 
 impl Station {
+    /// Fails if one of the fixed size arrays has the wrong length.
+    pub fn check_layout(&self) -> anyhow::Result<()> {
+        for (name, len, expected) in [
+            ("storage", self.storage.len(), 5),
+            ("unknown1", self.unknown1.len(), 192 - 30),
+            ("slots", self.slots.len(), 12),
+            ("unknown2", self.unknown2.len(), 320 - 192 - 48),
+        ] {
+            if len != expected {
+                return Err(crate::error::some_error(format!(
+                    "Station {} has {} entries, expected {}",
+                    name, len, expected
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of u32 words the station takes up when written. Storage entries are 6 words,
+    /// slots 4 and the header 9.
+    pub fn param_count(&self) -> usize {
+        self.storage.len() * 6
+            + self.unknown1.len()
+            + self.slots.len() * 4
+            + self.unknown2.len()
+            + 9
+            + self.unknown3.len()
+    }
+
     fn storage_count(&self) -> usize {
         if self.is_interstellar {
             5
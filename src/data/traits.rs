@@ -1,13 +1,13 @@
-use std::hash::Hash;
+use std::{collections::HashMap, hash::Hash, str::FromStr, sync::OnceLock};
 
-use strum::ParseError;
+use strum::{IntoEnumIterator, ParseError};
 
 use binrw::{BinRead, BinWrite};
 #[cfg(feature = "dump")]
-use serde::Deserialize;
+use std::borrow::Cow;
 
-use super::enums::{BPModel, DSPIcon, DSPItem, DSPRecipe};
-use crate::locale::LocalizedEnumImpl;
+use super::enums::{BPModel, DSPIcon, DSPItem, DSPRecipe, DSPSignal, DSPTech};
+use crate::locale::{all_names, LocalizedEnumImpl};
 
 pub trait DSPEnum:
     Eq
@@ -16,28 +16,236 @@ pub trait DSPEnum:
     + for<'a> TryFrom<&'a str, Error = ParseError>
     + TryFrom<Self::Underlying>
     + Into<Self::Underlying>
+    + IntoEnumIterator
+    + AsRef<str>
+    + 'static
 {
-    type Underlying: Copy;
+    type Underlying: Copy + FromStr;
     const PRETTY_NAME: &'static str;
+
+    /// Names in every locale that names this value.
+    fn localized_names(&self) -> Vec<&'static str>;
+
+    /// Values by normalized variant and localized names, built by `name_index`.
+    fn name_index() -> &'static HashMap<String, Vec<Self>>;
 }
 
 pub trait TryFromUserString: Sized {
     fn try_from_user_string(s: &str) -> anyhow::Result<Self>;
 }
 
+/// Lowercase letters and digits of a name, so "Conveyor belt MK.II" matches ConveyorBeltMKII.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(cur).min(row[j])
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// The variant name and every localized name of a value.
+fn user_names<T: DSPEnum>(v: &T) -> impl Iterator<Item = &str> {
+    std::iter::once(v.as_ref()).chain(v.localized_names())
+}
+
+fn name_index<T: DSPEnum>() -> HashMap<String, Vec<T>> {
+    let mut index: HashMap<String, Vec<T>> = HashMap::new();
+    for v in T::iter() {
+        for name in user_names(&v) {
+            let values = index.entry(normalize(name)).or_default();
+            if !values.contains(&v) {
+                values.push(v);
+            }
+        }
+    }
+    index
+}
+
+/// Up to three values whose names are close to `s`, as "Variant (closest name)".
+fn suggestions<T: DSPEnum>(s: &str) -> Vec<String> {
+    let input = normalize(s);
+    let limit = (input.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, String)> = T::iter()
+        .filter_map(|v| {
+            let (d, name) = user_names(&v)
+                .map(|name| {
+                    let n = normalize(name);
+                    let contained = n.chars().count().min(input.chars().count()) >= 2
+                        && (n.contains(&input) || input.contains(&n));
+                    let d = if contained {
+                        1
+                    } else {
+                        edit_distance(&input, &n)
+                    };
+                    (d, name)
+                })
+                .min_by_key(|(d, _)| *d)?;
+            let shown = if name == v.as_ref() {
+                name.to_owned()
+            } else {
+                format!("{} ({})", v.as_ref(), name)
+            };
+            (d <= limit).then_some((d, shown))
+        })
+        .collect();
+    scored.sort_by_key(|(d, _)| *d);
+    scored.into_iter().take(3).map(|(_, s)| s).collect()
+}
+
+/// Accepts variant names, numeric IDs and names in any locale, ignoring case, spaces and
+/// punctuation.
 impl<T: DSPEnum> TryFromUserString for T {
     fn try_from_user_string(s: &str) -> anyhow::Result<Self> {
-        Self::try_from(s).or_else(|_| anyhow::bail!("'{}' is not a known {}. Run 'dspbp items' or 'dspbp recipes' for a list of item/recipe names.", s, T::PRETTY_NAME))
+        if let Ok(v) = Self::try_from(s.trim()) {
+            return Ok(v);
+        }
+        if let Some(v) = s
+            .trim()
+            .parse::<T::Underlying>()
+            .ok()
+            .and_then(|n| T::try_from(n).ok())
+        {
+            return Ok(v);
+        }
+        let matches: &[T] = T::name_index()
+            .get(&normalize(s))
+            .map_or(&[], |m| m.as_slice());
+        match matches {
+            [v] => return Ok(*v),
+            [_, _, ..] => {
+                let names: Vec<&str> = matches.iter().map(|v| v.as_ref()).collect();
+                anyhow::bail!("'{}' could be any of: {}.", s, names.join(", "));
+            }
+            [] => (),
+        }
+        let suggestions = suggestions::<T>(s);
+        let hint = if suggestions.is_empty() {
+            String::new()
+        } else {
+            format!(" Did you mean {}?", suggestions.join(", "))
+        };
+        anyhow::bail!(
+            "'{}' is not a known {}.{} Run 'dspbp {}s' for a list of {} names.",
+            s,
+            T::PRETTY_NAME,
+            hint,
+            T::PRETTY_NAME,
+            T::PRETTY_NAME
+        )
+    }
+}
+
+/// Accepts "signal:Name", "tech:Name", "recipe:Name", "item:Name", a raw icon number, or a
+/// name, which is looked up as an item, then a recipe, signal and technology. Signals and
+/// technologies may also be given by number, like "signal:405".
+impl TryFromUserString for DSPIcon {
+    fn try_from_user_string(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        fn number_or<T: DSPEnum>(s: &str) -> anyhow::Result<u32>
+        where
+            u32: From<T>,
+        {
+            s.trim()
+                .parse::<u32>()
+                .or_else(|_| T::try_from_user_string(s).map(u32::from))
+        }
+        if let Some((kind, rest)) = s.split_once(':') {
+            match kind.trim().to_ascii_lowercase().as_str() {
+                "signal" => return Ok(DSPIcon::Signal(number_or::<DSPSignal>(rest)?)),
+                "tech" => return Ok(DSPIcon::Tech(number_or::<DSPTech>(rest)?)),
+                "recipe" => return Ok(DSPIcon::Recipe(DSPRecipe::try_from_user_string(rest)?)),
+                "item" => return Ok(DSPIcon::Item(DSPItem::try_from_user_string(rest)?)),
+                _ => (),
+            }
+        }
+        if let Ok(n) = s.parse::<u32>() {
+            return DSPIcon::try_from(n);
+        }
+        DSPItem::try_from_user_string(s)
+            .map(DSPIcon::Item)
+            .or_else(|e| {
+                DSPRecipe::try_from_user_string(s)
+                    .map(DSPIcon::Recipe)
+                    .or_else(|_| {
+                        DSPSignal::try_from_user_string(s).map(|v| DSPIcon::Signal(v.into()))
+                    })
+                    .or_else(|_| DSPTech::try_from_user_string(s).map(|v| DSPIcon::Tech(v.into())))
+                    .map_err(|_| e)
+            })
     }
 }
 
 impl DSPEnum for DSPRecipe {
     type Underlying = u16;
     const PRETTY_NAME: &'static str = "recipe";
+
+    fn localized_names(&self) -> Vec<&'static str> {
+        all_names(self).collect()
+    }
+
+    fn name_index() -> &'static HashMap<String, Vec<Self>> {
+        static INDEX: OnceLock<HashMap<String, Vec<DSPRecipe>>> = OnceLock::new();
+        INDEX.get_or_init(name_index)
+    }
 }
 impl DSPEnum for DSPItem {
     type Underlying = u16;
     const PRETTY_NAME: &'static str = "item";
+
+    fn localized_names(&self) -> Vec<&'static str> {
+        all_names(self).collect()
+    }
+
+    fn name_index() -> &'static HashMap<String, Vec<Self>> {
+        static INDEX: OnceLock<HashMap<String, Vec<DSPItem>>> = OnceLock::new();
+        INDEX.get_or_init(name_index)
+    }
+}
+
+impl DSPEnum for DSPSignal {
+    type Underlying = u16;
+    const PRETTY_NAME: &'static str = "signal";
+
+    fn localized_names(&self) -> Vec<&'static str> {
+        all_names(self).collect()
+    }
+
+    fn name_index() -> &'static HashMap<String, Vec<Self>> {
+        static INDEX: OnceLock<HashMap<String, Vec<DSPSignal>>> = OnceLock::new();
+        INDEX.get_or_init(name_index)
+    }
+}
+
+impl DSPEnum for DSPTech {
+    type Underlying = u16;
+    const PRETTY_NAME: &'static str = "tech";
+
+    fn localized_names(&self) -> Vec<&'static str> {
+        all_names(self).collect()
+    }
+
+    fn name_index() -> &'static HashMap<String, Vec<Self>> {
+        static INDEX: OnceLock<HashMap<String, Vec<DSPTech>>> = OnceLock::new();
+        INDEX.get_or_init(name_index)
+    }
 }
 
 macro_rules! from_into_boilerplate {
@@ -60,6 +268,8 @@ macro_rules! from_into_boilerplate {
 
 from_into_boilerplate!(u32, u16, DSPItem);
 from_into_boilerplate!(u32, u16, DSPRecipe);
+from_into_boilerplate!(u32, u16, DSPSignal);
+from_into_boilerplate!(u32, u16, DSPTech);
 
 // These are newtypes for various u16/u32 values in the blueprint. Help make sure we don't misuse
 // them and will allow for better localization in the future.
@@ -106,7 +316,6 @@ macro_rules! newtype_enum {
             fn base_mut(&mut self) -> &mut Self::Base;
         }
 
-        #[cfg_attr(feature = "dump", derive(Deserialize))]
         #[derive(BinRead, BinWrite, Debug, PartialEq, Eq, Clone, Copy)]
         pub struct $Id<T: Nice + TryInto<$DSP> + From<$DSP>>(pub T);
 
@@ -142,18 +351,192 @@ macro_rules! newtype_enum {
             {
                 let maybe_localized = match &self.0.try_into() {
                     Err(_) => None,
-                    Ok(v) => LocalizedEnumImpl::localize(v),
+                    Ok(v) => DumpName::dump_name(v),
                 };
                 match maybe_localized {
-                    Some(s) => serializer.serialize_str(s),
+                    Some(s) => serializer.serialize_str(&s),
                     None => self.0.serialize(serializer),
                 }
             }
         }
+
+        #[cfg(feature = "dump")]
+        impl<'de, T> serde::Deserialize<'de> for $Id<T>
+        where
+            T: Nice + TryInto<$DSP> + From<$DSP> + TryFrom<u64>,
+        {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deserializer
+                    .deserialize_any(NumberOrName::<$DSP, T>(std::marker::PhantomData))
+                    .map(Self)
+            }
+        }
+
+        #[cfg(feature = "dump")]
+        impl<T: schemars::JsonSchema + Nice + TryInto<$DSP> + From<$DSP>> schemars::JsonSchema
+            for $Id<T>
+        {
+            fn inline_schema() -> bool {
+                true
+            }
+
+            fn schema_name() -> Cow<'static, str> {
+                format!("{}_{}", stringify!($Id), T::schema_name()).into()
+            }
+
+            fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+                schemars::json_schema!({
+                    "anyOf": [
+                        generator.subschema_for::<T>(),
+                        { "type": "string", "description": <$DSP as DumpName>::NAME_HELP },
+                    ]
+                })
+            }
+        }
     };
 }
 
+/// Reads an ID in a dump, written as a number or, in human-readable dumps, as a name.
+#[cfg(feature = "dump")]
+struct NumberOrName<DSP, T>(std::marker::PhantomData<(DSP, T)>);
+
+#[cfg(feature = "dump")]
+impl<'de, DSP: DumpName, T: TryFrom<u64> + From<DSP>> serde::de::Visitor<'de>
+    for NumberOrName<DSP, T>
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "an ID number or a name")
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<T, E> {
+        T::try_from(v).map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<T, E> {
+        DSP::from_dump_name(v).map(T::from).map_err(E::custom)
+    }
+}
+
+/// Values that human-readable dumps write as names. Any name `try_from_user_string` accepts
+/// reads back as the same value.
+#[cfg(feature = "dump")]
+trait DumpName: Sized {
+    /// What names the schema accepts.
+    const NAME_HELP: &'static str;
+
+    fn dump_name(&self) -> Option<Cow<'static, str>>;
+    fn from_dump_name(s: &str) -> anyhow::Result<Self>;
+}
+
+#[cfg(feature = "dump")]
+impl DumpName for DSPItem {
+    const NAME_HELP: &'static str = "Item name in any locale";
+
+    fn dump_name(&self) -> Option<Cow<'static, str>> {
+        self.localize().map(Cow::Borrowed)
+    }
+
+    fn from_dump_name(s: &str) -> anyhow::Result<Self> {
+        Self::try_from_user_string(s)
+    }
+}
+
+#[cfg(feature = "dump")]
+impl DumpName for DSPRecipe {
+    const NAME_HELP: &'static str = "Recipe name in any locale";
+
+    fn dump_name(&self) -> Option<Cow<'static, str>> {
+        self.localize().map(Cow::Borrowed)
+    }
+
+    fn from_dump_name(s: &str) -> anyhow::Result<Self> {
+        Self::try_from_user_string(s)
+    }
+}
+
+/// Models are named after the building that uses them.
+#[cfg(feature = "dump")]
+impl DumpName for BPModel {
+    const NAME_HELP: &'static str = "Name of the building that uses the model, in any locale";
+
+    fn dump_name(&self) -> Option<Cow<'static, str>> {
+        self.localize().map(Cow::Borrowed)
+    }
+
+    fn from_dump_name(s: &str) -> anyhow::Result<Self> {
+        Self::from_building(DSPItem::try_from_user_string(s)?)
+    }
+}
+
+/// Items and recipes often share names, so recipe icons are written as "recipe:Name", and
+/// signals and technologies as "signal:Name" and "tech:Name".
+#[cfg(feature = "dump")]
+impl DumpName for DSPIcon {
+    const NAME_HELP: &'static str =
+        "Item name, or \"recipe:\", \"signal:\" or \"tech:\" and a name, in any locale";
+
+    fn dump_name(&self) -> Option<Cow<'static, str>> {
+        match self {
+            DSPIcon::Item(i) => i.dump_name(),
+            DSPIcon::Recipe(r) => Some(format!("recipe:{}", r.localize()?).into()),
+            DSPIcon::Signal(v) => {
+                let name = DSPSignal::try_from(*v).ok()?.localize()?;
+                Some(format!("signal:{}", name).into())
+            }
+            DSPIcon::Tech(v) => {
+                Some(format!("tech:{}", DSPTech::try_from(*v).ok()?.localize()?).into())
+            }
+            DSPIcon::Unknown(_) => None,
+        }
+    }
+
+    fn from_dump_name(s: &str) -> anyhow::Result<Self> {
+        Self::try_from_user_string(s)
+    }
+}
+
 newtype_enum!(DSPItem, ItemId, ItemIdTrait);
 newtype_enum!(DSPRecipe, RecipeId, RecipeIdTrait);
 newtype_enum!(DSPIcon, IconId, IconIdTrait);
 newtype_enum!(BPModel, BPModelId, BPModelIdItrait);
+
+#[cfg(test)]
+mod test {
+    use super::TryFromUserString;
+    use crate::data::enums::{DSPItem, DSPRecipe};
+
+    #[test]
+    fn user_strings() {
+        for s in [
+            "ConveyorBeltMKII",
+            "Conveyor belt MK.II",
+            "conveyor_belt_mk_ii",
+            "高速传送带",
+            "2002",
+        ] {
+            assert_eq!(
+                DSPItem::try_from_user_string(s).unwrap(),
+                DSPItem::ConveyorBeltMKII,
+                "{}",
+                s
+            );
+        }
+        assert_eq!(
+            DSPRecipe::try_from_user_string("gear").unwrap(),
+            DSPRecipe::Gear
+        );
+        let e = DSPItem::try_from_user_string("Conveyer Belt MK.II").unwrap_err();
+        assert!(
+            e.to_string().contains("Did you mean ConveyorBeltMKII"),
+            "{}",
+            e
+        );
+        let e = DSPItem::try_from_user_string("xyzzy").unwrap_err();
+        assert!(!e.to_string().contains("Did you mean"), "{}", e);
+    }
+}
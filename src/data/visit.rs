@@ -1,6 +1,7 @@
 use crate::blueprint::Blueprint;
 
 use super::{
+    assembler::Assembler,
     belt::Belt,
     blueprint::BlueprintData,
     building::Building,
@@ -35,6 +36,10 @@ pub trait Visitor {
     fn visit_belt(&mut self, v: &mut Belt) {
         v.visit(self)
     }
+
+    fn visit_assembler(&mut self, v: &mut Assembler) {
+        v.visit(self)
+    }
 }
 
 pub trait Visit {
@@ -0,0 +1,150 @@
+//! Versioned JSON dumps.
+//!
+//! Dumps carry a `format_version`. When a change to the dumped structs alters the JSON layout,
+//! bump `FORMAT_VERSION` and add a migration that rewrites the previous version's JSON into
+//! the new layout, so dumps kept around still undump.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{blueprint::Blueprint, data::building::BuildingParam, error::some_error};
+
+/// Version of the dump layout written by `dump_json`.
+pub const FORMAT_VERSION: u64 = 1;
+
+/// Rewrites a dump of one version into the layout of the next.
+type Migration = fn(&mut Value) -> anyhow::Result<()>;
+
+/// `MIGRATIONS[n]` migrates version `n` to `n + 1`.
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize] = [
+    // Version 0 dumps predate `format_version` and have the same layout as version 1.
+    |_| Ok(()),
+];
+
+#[derive(Serialize, JsonSchema)]
+#[schemars(title = "Blueprint dump")]
+struct Dump<'a> {
+    format_version: u64,
+    #[serde(flatten)]
+    blueprint: &'a Blueprint,
+}
+
+pub(crate) fn to_json(bp: &Blueprint) -> anyhow::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&Dump {
+        format_version: FORMAT_VERSION,
+        blueprint: bp,
+    })?)
+}
+
+pub(crate) fn schema() -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(&schemars::schema_for!(Dump))?)
+}
+
+/// Brings a dump of any earlier version up to `FORMAT_VERSION`. Dumps without a version are
+/// version 0.
+fn migrate(json: &mut Value) -> anyhow::Result<()> {
+    let Some(obj) = json.as_object_mut() else {
+        return Err(some_error("Invalid dump: expected a JSON object"));
+    };
+    let version = match obj.remove("format_version") {
+        None => 0,
+        Some(v) => v.as_u64().ok_or_else(|| {
+            some_error(format!(
+                "Invalid dump: format_version {} is not a number",
+                v
+            ))
+        })?,
+    };
+    if version > FORMAT_VERSION {
+        return Err(some_error(format!(
+            "Dump format version {} is newer than the supported version {}. Update dspbp.",
+            version, FORMAT_VERSION
+        )));
+    }
+    for m in MIGRATIONS[version as usize..].iter() {
+        m(json)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn from_json(json: &str) -> anyhow::Result<Blueprint> {
+    let mut value: Value = serde_json::from_str(json)?;
+    migrate(&mut value)?;
+    let bp: Blueprint = serde_path_to_error::deserialize(value)
+        .map_err(|e| some_error(format!("Invalid dump at {}: {}", e.path(), e.inner())))?;
+    check_params(&bp)?;
+    Ok(bp)
+}
+
+/// Checks what the JSON layout cannot: that every building's `parameter_count` matches its
+/// parameters and that stations have fixed size arrays of the right length. Either would
+/// otherwise encode to a blueprint the game cannot read.
+fn check_params(bp: &Blueprint) -> anyhow::Result<()> {
+    for (i, b) in bp.data.buildings.iter().enumerate() {
+        let name = format!("building #{} (index {})", i, b.header.index);
+        if let BuildingParam::Station(s) = &b.param {
+            s.check_layout()
+                .map_err(|e| some_error(format!("Invalid dump: {}: {}", name, e)))?;
+        }
+        let count = b.param.param_count();
+        if b.header.parameter_count as usize != count {
+            return Err(some_error(format!(
+                "Invalid dump: {} has parameter_count {}, but its parameters take {}",
+                name, b.header.parameter_count, count
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_json, to_json, FORMAT_VERSION};
+    use crate::{blueprint::Blueprint, testutil::get_file};
+
+    #[test]
+    fn versions() {
+        let f = get_file("旧版本5飞机.txt");
+        let bp = Blueprint::new(std::str::from_utf8(&f).unwrap().trim()).unwrap();
+        let mut json: serde_json::Value = serde_json::from_slice(&to_json(&bp).unwrap()).unwrap();
+        assert_eq!(json["format_version"], FORMAT_VERSION);
+
+        json.as_object_mut().unwrap().remove("format_version");
+        let old = from_json(&json.to_string()).unwrap();
+        assert_eq!(
+            old.into_bp_string(6).unwrap(),
+            bp.into_bp_string(6).unwrap()
+        );
+
+        json["format_version"] = (FORMAT_VERSION + 1).into();
+        let e = from_json(&json.to_string()).err().unwrap();
+        assert!(e.to_string().contains("newer"), "{}", e);
+    }
+
+    #[test]
+    fn rejects_bad_params() {
+        let f = get_file("新版本5飞机.txt");
+        let bp = Blueprint::new(std::str::from_utf8(&f).unwrap().trim()).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&to_json(&bp).unwrap()).unwrap();
+        let station = json["data"]["buildings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .position(|b| b["param"].get("Station").is_some())
+            .unwrap();
+
+        let mut bad = json.clone();
+        bad["data"]["buildings"][station]["header"]["parameter_count"] = 1.into();
+        let e = from_json(&bad.to_string()).err().unwrap();
+        assert!(e.to_string().contains("parameter_count 1"), "{}", e);
+
+        let mut bad = json;
+        bad["data"]["buildings"][station]["param"]["Station"]["slots"]
+            .as_array_mut()
+            .unwrap()
+            .pop();
+        let e = from_json(&bad.to_string()).err().unwrap();
+        assert!(e.to_string().contains("slots has 11 entries"), "{}", e);
+    }
+}
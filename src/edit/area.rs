@@ -0,0 +1,294 @@
+use std::fmt::Display;
+
+use crate::{
+    data::{area::Area, blueprint::BlueprintData},
+    error::some_error,
+};
+
+/// Summary of one blueprint area.
+#[derive(Debug)]
+pub struct AreaInfo {
+    pub index: i8,
+    pub parent_index: i8,
+    pub tropic_anchor: u16,
+    pub area_segments: u16,
+    pub width: u16,
+    pub height: u16,
+    /// Offset of the area's origin from its root area's origin, in grid cells.
+    pub origin: (f32, f32),
+    pub building_count: usize,
+    /// Bounding box of building positions as (min x, min y, max x, max y), in area coordinates.
+    pub extent: Option<(f32, f32, f32, f32)>,
+}
+
+impl Display for AreaInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Area {} (parent {}): {}x{} cells at ({}, {}), {} segments, tropic anchor {}, {} buildings",
+            self.index,
+            self.parent_index,
+            self.width,
+            self.height,
+            self.origin.0,
+            self.origin.1,
+            self.area_segments,
+            self.tropic_anchor,
+            self.building_count
+        )?;
+        if let Some((x0, y0, x1, y1)) = self.extent {
+            write!(f, ", extent ({}, {})..({}, {})", x0, y0, x1, y1)?;
+        }
+        Ok(())
+    }
+}
+
+fn find_area(data: &BlueprintData, index: i8) -> Option<&Area> {
+    data.areas.iter().find(|a| a.index == index)
+}
+
+/// Offset of an area's origin from the root of its parent chain.
+///
+/// Areas in other latitude bands have differently sized cells; offsets are added up as is,
+/// without rescaling.
+fn area_origin(data: &BlueprintData, index: i8) -> anyhow::Result<(f32, f32)> {
+    let (mut x, mut y) = (0.0, 0.0);
+    let mut area =
+        find_area(data, index).ok_or_else(|| some_error(format!("No area {}", index)))?;
+    for _ in 0..data.areas.len() {
+        if area.parent_index < 0 {
+            return Ok((x, y));
+        }
+        x += area.anchor_local_offset_x as f32;
+        y += area.anchor_local_offset_y as f32;
+        area = find_area(data, area.parent_index).ok_or_else(|| {
+            some_error(format!(
+                "Area {} has missing parent {}",
+                area.index, area.parent_index
+            ))
+        })?;
+    }
+    Err(some_error(format!(
+        "Area {} has a cyclic parent chain",
+        index
+    )))
+}
+
+/// Lists every area with its building count and extent.
+pub fn list_areas(data: &BlueprintData) -> Vec<AreaInfo> {
+    data.areas
+        .iter()
+        .map(|a| {
+            let mut extent: Option<(f32, f32, f32, f32)> = None;
+            let mut building_count = 0;
+            for b in data
+                .buildings
+                .iter()
+                .filter(|b| b.header.area_index == a.index)
+            {
+                building_count += 1;
+                let (x, y) = (b.header.local_offset_x, b.header.local_offset_y);
+                extent = Some(match extent {
+                    None => (x, y, x, y),
+                    Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                });
+            }
+            AreaInfo {
+                index: a.index,
+                parent_index: a.parent_index,
+                tropic_anchor: a.tropic_anchor,
+                area_segments: a.area_segments,
+                width: a.width,
+                height: a.height,
+                origin: area_origin(data, a.index).unwrap_or((0.0, 0.0)),
+                building_count,
+                extent,
+            }
+        })
+        .collect()
+}
+
+/// Finds structural problems with areas: a wrong area count, duplicate or missing areas,
+/// broken parent chains and buildings in areas that do not exist.
+pub fn area_problems(data: &BlueprintData) -> Vec<String> {
+    let mut problems = vec![];
+    if data.header.area_count as usize != data.areas.len() {
+        problems.push(format!(
+            "Header says {} areas, blueprint has {}",
+            data.header.area_count,
+            data.areas.len()
+        ));
+    }
+    for (i, a) in data.areas.iter().enumerate() {
+        if data.areas[..i].iter().any(|o| o.index == a.index) {
+            problems.push(format!("Duplicate area index {}", a.index));
+        }
+        if let Err(e) = area_origin(data, a.index) {
+            problems.push(e.to_string());
+        }
+    }
+    if find_area(data, data.header.primary_area_index as i8).is_none() {
+        problems.push(format!(
+            "Primary area {} does not exist",
+            data.header.primary_area_index
+        ));
+    }
+    for (i, b) in data.buildings.iter().enumerate() {
+        if find_area(data, b.header.area_index).is_none() {
+            problems.push(format!(
+                "Building #{} (index {}) is in area {}, which does not exist",
+                i, b.header.index, b.header.area_index
+            ));
+        }
+    }
+    problems
+}
+
+/// Fails with the first problem found by `area_problems`.
+pub fn validate_areas(data: &BlueprintData) -> anyhow::Result<()> {
+    match area_problems(data).into_iter().next() {
+        Some(p) => Err(some_error(p)),
+        None => Ok(()),
+    }
+}
+
+/// Moves the buildings at the given list positions into another area, keeping their position
+/// relative to the root area.
+pub fn move_buildings(data: &mut BlueprintData, positions: &[usize], to: i8) -> anyhow::Result<()> {
+    validate_areas(data)?;
+    let to_origin = area_origin(data, to)?;
+    for p in positions.iter() {
+        if *p >= data.buildings.len() {
+            return Err(some_error(format!("No building #{}", p)));
+        }
+    }
+    for p in positions.iter() {
+        let from = data.buildings[*p].header.area_index;
+        let from_origin = area_origin(data, from)?;
+        let (dx, dy) = (from_origin.0 - to_origin.0, from_origin.1 - to_origin.1);
+        let h = &mut data.buildings[*p].header;
+        h.area_index = to;
+        h.local_offset_x += dx;
+        h.local_offset_x2 += dx;
+        h.local_offset_y += dy;
+        h.local_offset_y2 += dy;
+    }
+    Ok(())
+}
+
+/// Collapses all areas into the root of the primary area's parent chain, moving every
+/// building there.
+///
+/// The root area becomes area 0 and grows to cover all buildings. Buildings are shifted so no
+/// position is negative. Returns the number of areas removed.
+pub fn flatten_areas(data: &mut BlueprintData) -> anyhow::Result<usize> {
+    validate_areas(data)?;
+    let mut root = data.header.primary_area_index as i8;
+    while let Some(parent) = find_area(data, root)
+        .map(|a| a.parent_index)
+        .filter(|p| *p >= 0)
+    {
+        root = parent;
+    }
+    let all: Vec<usize> = (0..data.buildings.len()).collect();
+    move_buildings(data, &all, root)?;
+
+    let (mut min_x, mut min_y) = (0.0f32, 0.0f32);
+    for b in data.buildings.iter() {
+        let h = &b.header;
+        min_x = min_x.min(h.local_offset_x).min(h.local_offset_x2);
+        min_y = min_y.min(h.local_offset_y).min(h.local_offset_y2);
+    }
+
+    let removed = data.areas.len() - 1;
+    data.areas.retain(|a| a.index == root);
+    let area = &mut data.areas[0];
+    for b in data.buildings.iter_mut() {
+        let h = &mut b.header;
+        h.area_index = 0;
+        h.local_offset_x -= min_x;
+        h.local_offset_x2 -= min_x;
+        h.local_offset_y -= min_y;
+        h.local_offset_y2 -= min_y;
+        for (x, y) in [
+            (h.local_offset_x, h.local_offset_y),
+            (h.local_offset_x2, h.local_offset_y2),
+        ] {
+            area.width = area.width.max(x.ceil() as u16 + 1);
+            area.height = area.height.max(y.ceil() as u16 + 1);
+        }
+    }
+    area.index = 0;
+    area.parent_index = -1;
+    area.anchor_local_offset_x = 0;
+    area.anchor_local_offset_y = 0;
+    data.header.area_count = 1;
+    data.header.primary_area_index = 0;
+    data.header.cursor_target_area = 0;
+    data.header.dragbox_size_x = data.header.dragbox_size_x.max(area.width as u32);
+    data.header.dragbox_size_y = data.header.dragbox_size_y.max(area.height as u32);
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{area_problems, flatten_areas, list_areas, move_buildings};
+    use crate::{builder::BlueprintBuilder, data::enums::DSPItem};
+
+    fn two_areas() -> crate::data::blueprint::BlueprintData {
+        let mut b = BlueprintBuilder::new();
+        b.belt_path(DSPItem::ConveyorBeltMKI, &[(0, 0), (3, 0)])
+            .unwrap();
+        let mut data = b.build().data;
+        let mut child = data.areas[0].clone();
+        child.index = 1;
+        child.parent_index = 0;
+        child.anchor_local_offset_x = 10;
+        child.anchor_local_offset_y = 2;
+        data.areas.push(child);
+        data.header.area_count = 2;
+        data
+    }
+
+    #[test]
+    fn move_and_flatten() {
+        let mut data = two_areas();
+        assert!(area_problems(&data).is_empty());
+        move_buildings(&mut data, &[2, 3], 1).unwrap();
+        let areas = list_areas(&data);
+        assert_eq!(areas[1].building_count, 2);
+        assert_eq!(areas[1].origin, (10.0, 2.0));
+        assert_eq!(areas[1].extent, Some((-8.0, -2.0, -7.0, -2.0)));
+
+        data.buildings[0].header.area_index = 5;
+        assert_eq!(area_problems(&data).len(), 1);
+        assert!(flatten_areas(&mut data).is_err());
+        data.buildings[0].header.area_index = 0;
+
+        assert_eq!(flatten_areas(&mut data).unwrap(), 1);
+        assert_eq!(data.areas.len(), 1);
+        let xs: Vec<f32> = data
+            .buildings
+            .iter()
+            .map(|b| b.header.local_offset_x)
+            .collect();
+        assert_eq!(xs, vec![0.0, 1.0, 2.0, 3.0]);
+        assert!(area_problems(&data).is_empty());
+    }
+
+    #[test]
+    fn flatten_child_primary() {
+        let mut data = two_areas();
+        move_buildings(&mut data, &[2, 3], 1).unwrap();
+        data.header.primary_area_index = 1;
+        flatten_areas(&mut data).unwrap();
+        let pos: Vec<(f32, f32)> = data
+            .buildings
+            .iter()
+            .map(|b| (b.header.local_offset_x, b.header.local_offset_y))
+            .collect();
+        assert_eq!(pos, vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0)]);
+        assert_eq!((data.areas[0].width, data.areas[0].parent_index), (4, -1));
+        assert!(area_problems(&data).is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use crate::data::{
+    blueprint::BlueprintData,
+    enums::{DSPIcon, DSPItem, DSPRecipe},
+};
+
+/// Number of icon slots in a blueprint.
+pub const ICON_SLOTS: usize = 5;
+
+/// Decodes an icon slot. Empty slots are `None`; values that do not decode are `Unknown`.
+pub fn decode_icon(v: u32) -> Option<DSPIcon> {
+    (v != 0).then(|| DSPIcon::try_from(v).unwrap_or(DSPIcon::Unknown(v)))
+}
+
+/// The recipe most buildings use, preferring the lower recipe ID on ties.
+pub fn dominant_recipe(data: &BlueprintData) -> Option<DSPRecipe> {
+    let mut counts: HashMap<DSPRecipe, usize> = HashMap::new();
+    for b in data.buildings.iter() {
+        if let Ok(r) = DSPRecipe::try_from(b.header.recipe_id) {
+            *counts.entry(r).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(r, n)| (*n, std::cmp::Reverse(u16::from(*r))))
+        .map(|(r, _)| r)
+}
+
+/// Icon for a blueprint's output: the item the dominant recipe is named after, or the recipe
+/// itself when no item has its name.
+pub fn auto_icon(data: &BlueprintData) -> Option<DSPIcon> {
+    let recipe = dominant_recipe(data)?;
+    Some(match DSPItem::try_from(recipe.as_ref()) {
+        Ok(item) => DSPIcon::Item(item),
+        Err(_) => DSPIcon::Recipe(recipe),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{auto_icon, decode_icon};
+    use crate::{
+        builder::BlueprintBuilder,
+        data::{
+            enums::{DSPIcon, DSPItem, DSPRecipe},
+            traits::TryFromUserString,
+        },
+    };
+
+    #[test]
+    fn parse_and_pick_icons() {
+        for (s, icon) in [
+            ("IronIngot", DSPIcon::Item(DSPItem::IronIngot)),
+            ("recipe:Gear", DSPIcon::Recipe(DSPRecipe::Gear)),
+            ("signal:405", DSPIcon::Signal(405)),
+            ("tech:1001", DSPIcon::Tech(1001)),
+            ("signal:number 7", DSPIcon::Signal(607)),
+            ("tech:电磁学", DSPIcon::Tech(1001)),
+            ("Electromagnetism", DSPIcon::Tech(1001)),
+            ("1104", DSPIcon::Item(DSPItem::CopperIngot)),
+        ] {
+            assert_eq!(DSPIcon::try_from_user_string(s).unwrap(), icon, "{}", s);
+            assert_eq!(
+                DSPIcon::try_from_user_string(&icon.to_string()).unwrap(),
+                icon
+            );
+        }
+        assert!(DSPIcon::try_from_user_string("signal:x").is_err());
+        assert_eq!(DSPIcon::Signal(601).to_string(), "signal:Number1");
+        assert_eq!(decode_icon(0), None);
+
+        let mut b = BlueprintBuilder::new();
+        for x in 0..3 {
+            b.assembler(
+                DSPItem::AssemblingMachineMkI,
+                x as f32 * 4.0,
+                0.0,
+                0.0,
+                DSPRecipe::Gear,
+            )
+            .unwrap();
+        }
+        b.assembler(DSPItem::ArcSmelter, 0.0, 4.0, 0.0, DSPRecipe::Magnet)
+            .unwrap();
+        assert_eq!(
+            auto_icon(&b.build().data),
+            Some(DSPIcon::Item(DSPItem::Gear))
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+use crate::data::{blueprint::BlueprintData, building::NO_OBJECT, enums::BPModel};
+
+use super::area::area_problems;
+
+/// Finds problems the game may reject or misplace: unknown items, buildings whose model does
+/// not match their item, links to buildings that do not exist and broken areas.
+pub fn lint(data: &BlueprintData) -> Vec<String> {
+    let mut problems = vec![];
+    let indices: HashSet<i32> = data.buildings.iter().map(|b| b.header.index).collect();
+    for (i, b) in data.buildings.iter().enumerate() {
+        let name = format!("Building #{} (index {})", i, b.header.index);
+        let item = match b.kind() {
+            Ok(item) => item,
+            Err(_) => {
+                problems.push(format!("{} has unknown item {}", name, b.header.item_id.0));
+                continue;
+            }
+        };
+        // The model table is incomplete, so only flag models known to belong elsewhere.
+        let model_index = b.header.model_index.0;
+        match (BPModel::try_from(model_index), BPModel::from_building(item)) {
+            (Err(_), Ok(expected)) => problems.push(format!(
+                "{} ({:?}) has model {}, expected {}",
+                name,
+                item,
+                model_index,
+                u16::from(expected)
+            )),
+            (Ok(model), expected) if expected.as_ref().ok() != Some(&model) => {
+                problems.push(format!(
+                    "{} ({:?}) has model {}, which is the model of {:?}",
+                    name,
+                    item,
+                    model_index,
+                    model.building()
+                ))
+            }
+            _ => (),
+        }
+        for (dir, other) in [
+            ("output", b.header.output_object_index),
+            ("input", b.header.input_object_index),
+        ] {
+            if other != NO_OBJECT && !indices.contains(&(other as i32)) {
+                problems.push(format!(
+                    "{} has its {} linked to building {}, which does not exist",
+                    name, dir, other
+                ));
+            }
+        }
+    }
+    problems.extend(area_problems(data));
+    problems
+}
+
+#[cfg(test)]
+mod test {
+    use super::lint;
+    use crate::{
+        blueprint::Blueprint,
+        data::enums::{BPModel, DSPItem},
+        testutil::get_file,
+    };
+
+    fn example(f: &str) -> Blueprint {
+        Blueprint::new(std::str::from_utf8(&get_file(f)).unwrap().trim()).unwrap()
+    }
+
+    #[test]
+    fn examples_are_clean() {
+        for f in ["旧版本5飞机.txt", "新版本5飞机.txt"] {
+            let bp = example(f);
+            assert_eq!(lint(&bp.data), Vec::<String>::new(), "{}", f);
+        }
+    }
+
+    #[test]
+    fn flags_wrong_model_and_dangling_link() {
+        let mut bp = example("新版本5飞机.txt");
+        bp.data.buildings[0].header.model_index = BPModel::ConveyorBeltMKI.into();
+        bp.data.buildings[1].header.output_object_index = 100_000;
+        let problems = lint(&bp.data);
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].contains(&format!("{:?}", DSPItem::ConveyorBeltMKI)));
+    }
+}
@@ -0,0 +1,226 @@
+//! Structured metadata kept in a block at the end of a blueprint description:
+//!
+//! ```text
+//! Smelts iron and copper.
+//! ---
+//! author: Alice
+//! version: 1.2
+//! tags: smelting, early
+//! ```
+//!
+//! The block is a `---` line followed only by `key: value` lines. Descriptions without one
+//! have no metadata, so ordinary text with colons is left alone.
+
+use std::path::{Path, PathBuf};
+
+use crate::{blueprint::Blueprint, error::some_error, verify::collect_blueprints};
+
+const SEPARATOR: &str = "---";
+
+/// The field holding comma separated tags.
+pub const TAGS: &str = "tags";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// The description without the metadata block.
+    pub text: String,
+    fields: Vec<(String, String)>,
+}
+
+fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+fn field(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim();
+    valid_key(key).then(|| (key.to_owned(), value.trim().to_owned()))
+}
+
+impl Metadata {
+    /// Splits a decoded description into text and metadata.
+    pub fn parse(desc: &str) -> Self {
+        let lines: Vec<&str> = desc.split('\n').collect();
+        for (i, line) in lines.iter().enumerate().rev() {
+            if line.trim() != SEPARATOR {
+                continue;
+            }
+            let fields: Option<Vec<_>> = lines[i + 1..]
+                .iter()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| field(l))
+                .collect();
+            if let Some(fields) = fields {
+                return Self {
+                    text: lines[..i].join("\n").trim_end().to_owned(),
+                    fields,
+                };
+            }
+            break;
+        }
+        Self {
+            text: desc.to_owned(),
+            fields: vec![],
+        }
+    }
+
+    /// The description with the metadata block, if there are any fields.
+    pub fn to_description(&self) -> String {
+        if self.fields.is_empty() {
+            return self.text.clone();
+        }
+        let mut s = self.text.trim_end().to_owned();
+        if !s.is_empty() {
+            s.push('\n');
+        }
+        s += SEPARATOR;
+        for (k, v) in self.fields.iter() {
+            s += &format!("\n{}: {}", k, v);
+        }
+        s
+    }
+
+    /// Fields in the order they appear.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Value of a field. Keys are case insensitive.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets a field, keeping its place if it exists. `None` or an empty value removes it.
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> anyhow::Result<()> {
+        let key = key.trim();
+        if !valid_key(key) {
+            return Err(some_error(format!(
+                "Invalid metadata key '{}'. Use letters, digits, '_' and '-'",
+                key
+            )));
+        }
+        let value = value.map(str::trim).filter(|v| !v.is_empty());
+        if value.is_some_and(|v| v.contains('\n')) {
+            return Err(some_error(format!(
+                "Metadata value for '{}' spans lines",
+                key
+            )));
+        }
+        let existing = self
+            .fields
+            .iter()
+            .position(|(k, _)| k.eq_ignore_ascii_case(key));
+        match (existing, value) {
+            (Some(i), Some(v)) => self.fields[i].1 = v.to_owned(),
+            (Some(i), None) => {
+                self.fields.remove(i);
+            }
+            (None, Some(v)) => self.fields.push((key.to_owned(), v.to_owned())),
+            (None, None) => (),
+        }
+        Ok(())
+    }
+
+    /// The comma separated values of the tags field.
+    pub fn tags(&self) -> Vec<&str> {
+        self.get(TAGS)
+            .map(|t| {
+                t.split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether the tags include `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags()
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+/// Metadata of one file of a library.
+pub type FileMetadata = (PathBuf, anyhow::Result<Metadata>);
+
+/// Reads the metadata of every *.txt blueprint in a directory, recursively. With a tag, only
+/// returns blueprints that have it, and files that failed to parse.
+pub fn search(dir: &Path, tag: Option<&str>) -> std::io::Result<Vec<FileMetadata>> {
+    let mut files = vec![];
+    collect_blueprints(dir, &mut files)?;
+    files.sort();
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let res = std::fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|d| Ok(String::from_utf8(d)?))
+                .and_then(|s| Blueprint::new(s.trim()))
+                .and_then(|bp| Ok(Metadata::parse(&bp.get_description()?)));
+            (path, res)
+        })
+        .filter(|(_, res)| match (res, tag) {
+            (Ok(m), Some(tag)) => m.has_tag(tag),
+            _ => true,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{search, Metadata};
+    use crate::{blueprint::Blueprint, edit::EditBlueprint, testutil::get_file};
+
+    #[test]
+    fn parse_and_write() {
+        let m = Metadata::parse("Iron\n---\nauthor: Alice\n\ntags: smelting, Early\n");
+        assert_eq!(m.text, "Iron");
+        assert_eq!(m.get("Author"), Some("Alice"));
+        assert_eq!(m.tags(), vec!["smelting", "Early"]);
+        assert!(m.has_tag("early"));
+        assert_eq!(
+            m.to_description(),
+            "Iron\n---\nauthor: Alice\ntags: smelting, Early"
+        );
+
+        // A rule followed by prose is not a metadata block.
+        let prose = "Notes\n---\nSpeed: 11.25/min\nworks on any planet";
+        let mut m = Metadata::parse(prose);
+        assert_eq!((m.text.as_str(), m.fields()), (prose, &[][..]));
+        m.set("version", Some("2")).unwrap();
+        assert_eq!(Metadata::parse(&m.to_description()).text, prose);
+        m.set("VERSION", None).unwrap();
+        assert_eq!(m.to_description(), prose);
+        assert!(m.set("two words", Some("x")).is_err());
+        assert!(m.set("author", Some("a\nb")).is_err());
+    }
+
+    #[test]
+    fn search_by_tag() {
+        let base = std::env::temp_dir().join(format!("dspbp-meta-{}", std::process::id()));
+        std::fs::create_dir_all(&base).unwrap();
+        let raw = get_file("旧版本5飞机.txt");
+        let mut bp =
+            EditBlueprint::new(Blueprint::new(std::str::from_utf8(&raw).unwrap().trim()).unwrap());
+        bp.set_metadata("tags", Some("ships, late")).unwrap();
+        std::fs::write(base.join("tagged.txt"), bp.0.into_bp_string(6).unwrap()).unwrap();
+        std::fs::write(base.join("plain.txt"), &raw).unwrap();
+
+        let found = search(&base, Some("Ships")).unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(found[0].0.ends_with("tagged.txt"));
+        assert_eq!(
+            found[0].1.as_ref().unwrap().get("tags"),
+            Some("ships, late")
+        );
+        assert_eq!(search(&base, None).unwrap().len(), 2);
+        std::fs::remove_dir_all(base).unwrap();
+    }
+}
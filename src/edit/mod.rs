@@ -5,19 +5,35 @@ use anyhow::Result;
 use crate::{
     blueprint::Blueprint,
     data::{
-        enums::{BuildingClass, DSPItem, DSPRecipe},
+        assembler::ProliferatorMode,
+        enums::{BPModel, BuildingClass, DSPIcon, DSPItem, DSPRecipe},
         traits::DSPEnum,
         visit::Visitor,
     },
 };
 
 use self::{
+    area::AreaInfo,
+    proliferator::SetProliferatorMode,
     replace::{Replace, ReplaceBuilding, ReplaceItem, ReplaceRecipe},
+    select::{Selector, Where},
+    station::{SetStation, StationSettings},
     stats::GetStats,
+    tile::TileOptions,
+    upgrade::{TierChange, UpgradeReport},
 };
 
+pub mod area;
+pub mod icon;
+pub mod lint;
+pub mod meta;
+pub(crate) mod proliferator;
 pub(crate) mod replace;
+pub mod select;
+pub mod station;
 pub(crate) mod stats;
+pub mod tile;
+pub mod upgrade;
 
 fn map_using_map<T: DSPEnum + 'static>(m: HashMap<T, T>) -> Box<Replace<T>> {
     Box::new(move |from| *m.get(&from).unwrap_or(&from))
@@ -42,25 +58,126 @@ impl EditBlueprint {
         self.0.get_description()
     }
 
+    pub fn set_description(&mut self, text: &str) {
+        self.0.set_description(text);
+    }
+
+    /// The metadata block at the end of the description.
+    pub fn metadata(&self) -> Result<meta::Metadata> {
+        Ok(meta::Metadata::parse(&self.get_description()?))
+    }
+
+    /// Sets or, with `None`, removes a metadata field, keeping the rest of the description.
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> Result<()> {
+        let mut m = self.metadata()?;
+        m.set(key, value)?;
+        self.set_description(&m.to_description());
+        Ok(())
+    }
+
+    pub fn get_timestamp(&self) -> String {
+        self.0.get_timestamp()
+    }
+
+    pub fn set_timestamp(&mut self, date: &str) -> Result<()> {
+        self.0.set_timestamp(date)
+    }
+
+    pub fn get_layout(&self) -> u32 {
+        self.0.layout
+    }
+
+    pub fn set_layout(&mut self, layout: u32) {
+        self.0.set_layout(layout);
+    }
+
+    /// Sets blueprint icons. Icons use DSP encoding: item IDs, recipe IDs + 20000, or 0.
+    pub fn set_icons(&mut self, icons: &[u32]) -> Result<()> {
+        if icons.len() > 5 {
+            let e: crate::error::Error =
+                format!("Expected at most 5 icons, got {}", icons.len()).into();
+            return Err(e.into());
+        }
+        self.0.icons = [0; 5];
+        self.0.icons[..icons.len()].copy_from_slice(icons);
+        Ok(())
+    }
+
+    /// The icons in the 5 slots. Empty slots are `None`.
+    pub fn icons(&self) -> [Option<DSPIcon>; icon::ICON_SLOTS] {
+        self.0.icons.map(icon::decode_icon)
+    }
+
+    /// Sets or clears the icon in a slot, 0 to 4.
+    pub fn set_icon(&mut self, slot: usize, icon: Option<DSPIcon>) -> Result<()> {
+        if slot >= icon::ICON_SLOTS {
+            let e: crate::error::Error = format!("Icon slot {} out of range (0-4)", slot).into();
+            return Err(e.into());
+        }
+        self.0.icons[slot] = icon.map_or(0, u32::from);
+        Ok(())
+    }
+
+    /// Sets the first icon to the output of the recipe most buildings use. Returns the icon, or
+    /// `None` if no building has a recipe.
+    pub fn auto_icon(&mut self) -> Option<DSPIcon> {
+        let icon = icon::auto_icon(&self.0.data)?;
+        self.0.icons[0] = icon.into();
+        Some(icon)
+    }
+
     pub fn info(&mut self) -> Result<String> {
         let mut stats = GetStats::new();
         stats.visit_blueprint(&mut self.0);
         Ok(format!("{}", stats.0))
     }
 
+    fn run<V: Visitor>(&mut self, v: &mut V, selector: Option<&Selector>) {
+        match selector {
+            Some(s) => Where::new(s, v).visit_blueprint(&mut self.0),
+            None => v.visit_blueprint(&mut self.0),
+        }
+    }
+
     pub fn replace_item(&mut self, map: HashMap<DSPItem, DSPItem>) {
+        self.replace_item_where(map, None)
+    }
+
+    /// Like `replace_item`, only for buildings matching the selector. Blueprint icons are
+    /// only replaced when there is no selector.
+    pub fn replace_item_where(
+        &mut self,
+        map: HashMap<DSPItem, DSPItem>,
+        selector: Option<&Selector>,
+    ) {
         let m = map_using_map(map);
         let mut r = ReplaceItem::new(&m);
-        r.visit_blueprint(&mut self.0);
+        self.run(&mut r, selector);
     }
 
     pub fn replace_recipe(&mut self, map: HashMap<DSPRecipe, DSPRecipe>) {
+        self.replace_recipe_where(map, None)
+    }
+
+    pub fn replace_recipe_where(
+        &mut self,
+        map: HashMap<DSPRecipe, DSPRecipe>,
+        selector: Option<&Selector>,
+    ) {
         let m = map_using_map(map);
         let mut r = ReplaceRecipe::new(&m);
-        r.visit_blueprint(&mut self.0);
+        self.run(&mut r, selector);
     }
 
     pub fn replace_both(&mut self, r: HashMap<DSPItem, DSPItem>) {
+        self.replace_both_where(r, None)
+    }
+
+    pub fn replace_both_where(
+        &mut self,
+        r: HashMap<DSPItem, DSPItem>,
+        selector: Option<&Selector>,
+    ) {
         let r2: HashMap<DSPRecipe, DSPRecipe> = r
             .iter()
             .filter_map(|(k, v)| {
@@ -69,23 +186,105 @@ impl EditBlueprint {
                 Some((k, v))
             })
             .collect();
-        self.replace_item(r);
-        self.replace_recipe(r2);
+        self.replace_item_where(r, selector);
+        self.replace_recipe_where(r2, selector);
     }
 
     pub fn replace_building(&mut self, map: HashMap<DSPItem, DSPItem>) -> Result<()> {
+        self.replace_building_where(map, None)
+    }
+
+    pub fn replace_building_where(
+        &mut self,
+        map: HashMap<DSPItem, DSPItem>,
+        selector: Option<&Selector>,
+    ) -> Result<()> {
         map.iter().try_for_each(|(i, o)| {
             if !BuildingClass::replacement_is_valid(*i, *o) {
                 let e: crate::error::Error =
                     format!("Cannot replace buildings: {} -> {}", i.as_ref(), o.as_ref()).into();
                 Err(anyhow::Error::from(e))
+            } else if BPModel::from_building(*o).is_err() {
+                let e: crate::error::Error =
+                    format!("Cannot replace buildings: no model for {}", o.as_ref()).into();
+                Err(anyhow::Error::from(e))
             } else {
                 Ok(())
             }
         })?;
         let m = map_using_map(map);
         let mut r = ReplaceBuilding::new(&m);
-        r.visit_blueprint(&mut self.0);
+        self.run(&mut r, selector);
+        Ok(())
+    }
+
+    /// Moves buildings matching the selector up or down their upgrade family.
+    ///
+    /// Buildings whose replacement has no known model are left alone and listed in the
+    /// report.
+    pub fn change_tiers(
+        &mut self,
+        change: &TierChange,
+        selector: Option<&Selector>,
+    ) -> Result<UpgradeReport> {
+        change.check()?;
+        let (map, report) = upgrade::plan(&self.0.data, change, selector);
+        if !map.is_empty() {
+            self.replace_building_where(map, selector)?;
+        }
+        Ok(report)
+    }
+
+    /// Upgrades every building to `tier` of its family, or to the highest tier.
+    pub fn upgrade_all(&mut self, tier: Option<usize>) -> Result<UpgradeReport> {
+        self.change_tiers(&TierChange::upgrade_all(tier), None)
+    }
+
+    /// Downgrades every building to `tier` of its family, or to the lowest tier.
+    pub fn downgrade_all(&mut self, tier: Option<usize>) -> Result<UpgradeReport> {
+        self.change_tiers(&TierChange::downgrade_all(tier), None)
+    }
+
+    /// Changes settings of logistic stations matching the selector.
+    pub fn set_station_where(
+        &mut self,
+        settings: &StationSettings,
+        selector: Option<&Selector>,
+    ) -> Result<()> {
+        let mut s = SetStation::new(settings)?;
+        self.run(&mut s, selector);
         Ok(())
     }
+
+    /// Sets the proliferator mode of production buildings matching the selector. Returns the
+    /// number of buildings changed.
+    pub fn set_proliferator_mode_where(
+        &mut self,
+        mode: ProliferatorMode,
+        selector: Option<&Selector>,
+    ) -> usize {
+        let mut s = SetProliferatorMode::new(mode);
+        self.run(&mut s, selector);
+        s.changed
+    }
+
+    /// Replicates the blueprint in a grid. Returns the number of belt links added between
+    /// copies.
+    pub fn tile(&mut self, opts: &TileOptions) -> Result<usize> {
+        tile::tile(&mut self.0.data, opts)
+    }
+
+    pub fn areas(&self) -> Vec<AreaInfo> {
+        area::list_areas(&self.0.data)
+    }
+
+    /// Moves buildings, by position in the building list, into another area.
+    pub fn move_to_area(&mut self, positions: &[usize], to: i8) -> Result<()> {
+        area::move_buildings(&mut self.0.data, positions, to)
+    }
+
+    /// Collapses all areas into one. Returns the number of areas removed.
+    pub fn flatten_areas(&mut self) -> Result<usize> {
+        area::flatten_areas(&mut self.0.data)
+    }
 }
@@ -0,0 +1,26 @@
+use crate::data::{
+    assembler::{Assembler, ProliferatorMode},
+    visit::{Visit, Visitor},
+};
+
+/// Sets the proliferator mode of production buildings, counting the ones that changed.
+pub struct SetProliferatorMode {
+    mode: ProliferatorMode,
+    pub changed: usize,
+}
+
+impl SetProliferatorMode {
+    pub fn new(mode: ProliferatorMode) -> Self {
+        Self { mode, changed: 0 }
+    }
+}
+
+impl Visitor for SetProliferatorMode {
+    fn visit_assembler(&mut self, v: &mut Assembler) {
+        if v.mode() != self.mode {
+            v.set_mode(self.mode);
+            self.changed += 1;
+        }
+        v.visit(self)
+    }
+}
@@ -0,0 +1,354 @@
+//! Building selectors for targeted edits.
+//!
+//! A selector is a list of conditions joined with `and`, `or` and `not`, with parentheses
+//! for grouping. `and` binds tighter than `or`. Conditions are:
+//!
+//! * `item=Name`, `item!=Name` - building item.
+//! * `recipe=Name`, `recipe!=Name` - building recipe.
+//! * `area=N` - blueprint area index.
+//! * `index=N`, `index in A..B`, `index in A..=B` - building index.
+//! * `bbox=X1,Y1,X2,Y2` - position within a box, edges included.
+//!
+//! Names can be quoted with double quotes, e.g. `item="Arc smelter"`.
+//!
+//! ```
+//! use dspbp::edit::select::Selector;
+//!
+//! let s: Selector = "item=AssemblingMachineMkI and (recipe=Gear or index in 10..40)"
+//!     .parse()
+//!     .unwrap();
+//! ```
+
+use std::{ops::RangeInclusive, str::FromStr};
+
+use crate::{
+    blueprint::Blueprint,
+    data::{
+        blueprint::BlueprintData,
+        building::Building,
+        enums::{DSPItem, DSPRecipe},
+        traits::TryFromUserString,
+        visit::{Visit, Visitor},
+    },
+    error::some_error,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    Item(DSPItem),
+    Recipe(DSPRecipe),
+    Area(i8),
+    Index(RangeInclusive<i32>),
+    BBox { x1: f32, y1: f32, x2: f32, y2: f32 },
+    Not(Box<Selector>),
+    And(Box<Selector>, Box<Selector>),
+    Or(Box<Selector>, Box<Selector>),
+}
+
+impl Selector {
+    pub fn matches(&self, b: &Building) -> bool {
+        let h = &b.header;
+        match self {
+            Self::Item(i) => h.item_id == (*i).into(),
+            Self::Recipe(r) => h.recipe_id == (*r).into(),
+            Self::Area(a) => h.area_index == *a,
+            Self::Index(r) => r.contains(&h.index),
+            Self::BBox { x1, y1, x2, y2 } => {
+                (*x1..=*x2).contains(&h.local_offset_x) && (*y1..=*y2).contains(&h.local_offset_y)
+            }
+            Self::Not(s) => !s.matches(b),
+            Self::And(a, c) => a.matches(b) && c.matches(b),
+            Self::Or(a, c) => a.matches(b) || c.matches(b),
+        }
+    }
+
+    /// Positions in the building list of all matching buildings.
+    pub fn positions(&self, data: &BlueprintData) -> Vec<usize> {
+        data.buildings
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| self.matches(b))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Eq,
+    Ne,
+    LParen,
+    RParen,
+}
+
+fn tokenize(s: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            '=' => tokens.push(Token::Eq),
+            '!' if chars.peek() == Some(&'=') => {
+                chars.next();
+                tokens.push(Token::Ne);
+            }
+            '"' => {
+                let mut w = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => w.push(c),
+                        None => return Err(some_error(format!("Unterminated quote in \"{}\"", s))),
+                    }
+                }
+                tokens.push(Token::Word(w));
+            }
+            c => {
+                let mut w = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    // The '=' of an inclusive range, like 1..=5, is part of the word.
+                    if c.is_whitespace()
+                        || ("()=!\"".contains(c) && !(c == '=' && w.ends_with("..")))
+                    {
+                        break;
+                    }
+                    w.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(w));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> anyhow::Result<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t.ok_or_else(|| some_error("Unexpected end of selector"))
+    }
+
+    fn keyword(&mut self, k: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(k) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn word(&mut self) -> anyhow::Result<String> {
+        match self.next()? {
+            Token::Word(w) => Ok(w),
+            t => Err(some_error(format!("Expected a value, got {:?}", t))),
+        }
+    }
+
+    fn or(&mut self) -> anyhow::Result<Selector> {
+        let mut s = self.and()?;
+        while self.keyword("or") {
+            s = Selector::Or(Box::new(s), Box::new(self.and()?));
+        }
+        Ok(s)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Selector> {
+        let mut s = self.not()?;
+        while self.keyword("and") {
+            s = Selector::And(Box::new(s), Box::new(self.not()?));
+        }
+        Ok(s)
+    }
+
+    fn not(&mut self) -> anyhow::Result<Selector> {
+        if self.keyword("not") {
+            return Ok(Selector::Not(Box::new(self.not()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let s = self.or()?;
+            return match self.next()? {
+                Token::RParen => Ok(s),
+                t => Err(some_error(format!("Expected ')', got {:?}", t))),
+            };
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> anyhow::Result<Selector> {
+        let field = self.word()?.to_ascii_lowercase();
+        let negate = match self.next()? {
+            Token::Eq => false,
+            Token::Ne => true,
+            Token::Word(w) if w.eq_ignore_ascii_case("in") && field == "index" => false,
+            t => {
+                return Err(some_error(format!(
+                    "Expected '=' or '!=' after {}, got {:?}",
+                    field, t
+                )))
+            }
+        };
+        let value = self.word()?;
+        let s = match field.as_str() {
+            "item" => Selector::Item(DSPItem::try_from_user_string(&value)?),
+            "recipe" => Selector::Recipe(DSPRecipe::try_from_user_string(&value)?),
+            "area" => Selector::Area(parse_num(&value)?),
+            "index" => Selector::Index(parse_range(&value)?),
+            "bbox" => {
+                let v = value
+                    .split(',')
+                    .map(parse_num::<f32>)
+                    .collect::<anyhow::Result<Vec<f32>>>()?;
+                if v.len() != 4 {
+                    return Err(some_error(format!(
+                        "Expected bbox=x1,y1,x2,y2, got \"{}\"",
+                        value
+                    )));
+                }
+                Selector::BBox {
+                    x1: v[0].min(v[2]),
+                    y1: v[1].min(v[3]),
+                    x2: v[0].max(v[2]),
+                    y2: v[1].max(v[3]),
+                }
+            }
+            f => return Err(some_error(format!("Unknown selector field \"{}\"", f))),
+        };
+        Ok(match negate {
+            true => Selector::Not(Box::new(s)),
+            false => s,
+        })
+    }
+}
+
+fn parse_num<T: FromStr>(s: &str) -> anyhow::Result<T> {
+    s.trim()
+        .parse()
+        .map_err(|_| some_error(format!("Invalid number \"{}\"", s)))
+}
+
+fn parse_range(s: &str) -> anyhow::Result<RangeInclusive<i32>> {
+    if let Some((a, b)) = s.split_once("..=") {
+        Ok(parse_num(a)?..=parse_num(b)?)
+    } else if let Some((a, b)) = s.split_once("..") {
+        let end = parse_num::<i32>(b)?
+            .checked_sub(1)
+            .ok_or_else(|| some_error(format!("Invalid range end \"{}\"", b)))?;
+        Ok(parse_num(a)?..=end)
+    } else {
+        let i = parse_num(s)?;
+        Ok(i..=i)
+    }
+}
+
+impl FromStr for Selector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let sel = p.or()?;
+        match p.peek() {
+            None => Ok(sel),
+            Some(t) => Err(some_error(format!(
+                "Unexpected {:?} in selector \"{}\"",
+                t, s
+            ))),
+        }
+    }
+}
+
+/// Runs a visitor only on the buildings matched by a selector.
+///
+/// Blueprint-level data like icons is left alone.
+pub struct Where<'a, V: Visitor> {
+    selector: &'a Selector,
+    inner: &'a mut V,
+}
+
+impl<'a, V: Visitor> Where<'a, V> {
+    pub fn new(selector: &'a Selector, inner: &'a mut V) -> Self {
+        Self { selector, inner }
+    }
+}
+
+impl<'a, V: Visitor> Visitor for Where<'a, V> {
+    fn visit_blueprint(&mut self, v: &mut Blueprint) {
+        v.visit(self)
+    }
+
+    fn visit_building(&mut self, v: &mut Building) {
+        if self.selector.matches(v) {
+            self.inner.visit_building(v)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Selector;
+    use crate::{
+        builder::BlueprintBuilder,
+        data::enums::{DSPItem, DSPRecipe},
+    };
+
+    #[test]
+    fn parse_and_match() {
+        let mut b = BlueprintBuilder::new();
+        b.assembler(
+            DSPItem::AssemblingMachineMkI,
+            0.0,
+            0.0,
+            0.0,
+            DSPRecipe::Gear,
+        )
+        .unwrap();
+        b.assembler(
+            DSPItem::AssemblingMachineMkI,
+            5.0,
+            0.0,
+            0.0,
+            DSPRecipe::Magnet,
+        )
+        .unwrap();
+        b.belt_path(DSPItem::ConveyorBeltMKI, &[(0, 3), (5, 3)])
+            .unwrap();
+        let data = b.build().data;
+        let sel = |s: &str| s.parse::<Selector>().unwrap().positions(&data);
+
+        assert_eq!(sel("item=AssemblingMachineMkI and recipe=Gear"), vec![0]);
+        assert_eq!(sel("item=AssemblingMachineMkI and recipe!=Gear"), vec![1]);
+        assert_eq!(sel("index in 1..3"), vec![1, 2]);
+        assert_eq!(sel("index in 6..=100 or index=0"), vec![0, 6, 7]);
+        assert_eq!(sel("bbox=4,-1,6,0"), vec![1]);
+        assert_eq!(sel("area=0 and not (item=ConveyorBeltMKI)").len(), 2);
+
+        for bad in [
+            "item=NoSuchThing",
+            "item=",
+            "index in 1..x",
+            "index in 0..-2147483648",
+            "colour=red",
+            "(area=0",
+            "area=0 area=1",
+        ] {
+            assert!(bad.parse::<Selector>().is_err(), "{}", bad);
+        }
+    }
+}
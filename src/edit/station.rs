@@ -0,0 +1,111 @@
+#[cfg(feature = "script")]
+use serde::Deserialize;
+
+use crate::{
+    data::{
+        station::{Station, StationHeader},
+        visit::{Visit, Visitor},
+    },
+    error::some_error,
+};
+
+/// Logistic station settings to change. Unset fields are left alone.
+#[cfg_attr(feature = "script", derive(Deserialize))]
+#[cfg_attr(feature = "script", serde(deny_unknown_fields))]
+#[derive(Debug, Default, Clone)]
+pub struct StationSettings {
+    /// Maximum charging power in MW.
+    pub charging_power_mw: Option<u32>,
+    /// Drone range as the angle from the station, in degrees.
+    pub drone_range_degrees: Option<u32>,
+    /// Vessel range in light years.
+    pub vessel_range_ly: Option<u32>,
+    /// Distance at which vessels start warping, in AU.
+    pub warp_distance_au: Option<f32>,
+    pub orbital_collector: Option<bool>,
+    /// Require warpers for vessels.
+    pub equip_warper: Option<bool>,
+    /// Minimum drone load, in percent.
+    pub drone_min_load: Option<u32>,
+    /// Minimum vessel load, in percent.
+    pub vessel_min_load: Option<u32>,
+    /// Output stacking.
+    pub piler_count: Option<u32>,
+}
+
+impl StationSettings {
+    /// Fails if a setting does not fit the station's fields.
+    pub fn check(&self) -> anyhow::Result<()> {
+        let too_large = |name: &str, value: String| {
+            Err(some_error(format!(
+                "Station {} {} is too large",
+                name, value
+            )))
+        };
+        if let Some(p) = self.charging_power_mw {
+            if p as u64 * 1_000_000 / 60 > u32::MAX as u64 {
+                return too_large("charging power", format!("{} MW", p));
+            }
+        }
+        if let Some(r) = self.vessel_range_ly {
+            if r.checked_mul(StationHeader::LY as u32).is_none() {
+                return too_large("vessel range", format!("{} LY", r));
+            }
+        }
+        if let Some(d) = self.warp_distance_au {
+            let max = (u32::MAX as usize / StationHeader::AU) as f32;
+            if !(0.0..=max).contains(&d) {
+                return Err(some_error(format!(
+                    "Station warp distance must be between 0 and {} AU, got {}",
+                    max, d
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct SetStation<'a>(&'a StationSettings);
+
+impl<'a> SetStation<'a> {
+    pub fn new(s: &'a StationSettings) -> anyhow::Result<Self> {
+        s.check()?;
+        Ok(Self(s))
+    }
+}
+
+impl<'a> Visitor for SetStation<'a> {
+    fn visit_station(&mut self, v: &mut Station) {
+        let s = self.0;
+        let h = &mut v.header;
+        if let Some(p) = s.charging_power_mw {
+            // Stored per tick, at 60 ticks per second.
+            h.work_energy_per_tick = (p as u64 * 1_000_000 / 60) as u32;
+        }
+        if let Some(a) = s.drone_range_degrees {
+            h.drone_range = StationHeader::angle_to_drone_range(a as usize);
+        }
+        if let Some(r) = s.vessel_range_ly {
+            h.vessel_range = r * StationHeader::LY as u32;
+        }
+        if let Some(d) = s.warp_distance_au {
+            h.warp_distance = (d * StationHeader::AU as f32).round() as u32;
+        }
+        if let Some(o) = s.orbital_collector {
+            h.orbital_collector = o as u32;
+        }
+        if let Some(w) = s.equip_warper {
+            h.equip_warper = w as u32;
+        }
+        if let Some(l) = s.drone_min_load {
+            h.drone_min_capacity = l;
+        }
+        if let Some(l) = s.vessel_min_load {
+            h.vessel_min_capacity = l;
+        }
+        if let Some(p) = s.piler_count {
+            h.piler_count = p;
+        }
+        v.visit(self)
+    }
+}
@@ -22,6 +22,11 @@ impl Visitor for GetStats {
         v.visit(self)
     }
 
+    fn visit_assembler(&mut self, v: &mut crate::data::assembler::Assembler) {
+        self.0.add_proliferator_mode(v.mode());
+        v.visit(self)
+    }
+
     fn visit_station_storage(&mut self, v: &mut crate::data::station::StationStorage) {
         if let Ok(b) = v.item_id.try_into() {
             self.0.add_station_ware(b);
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use crate::{
+    data::{
+        blueprint::BlueprintData,
+        building::{Building, NO_OBJECT},
+    },
+    error::some_error,
+};
+
+/// How to lay out copies of a blueprint.
+pub struct TileOptions {
+    pub columns: u32,
+    pub rows: u32,
+    /// Distance between neighbouring copies in grid cells. Defaults to the blueprint size.
+    pub spacing_x: Option<f32>,
+    pub spacing_y: Option<f32>,
+    /// Connect belt outputs of every copy to belt inputs of the next copy in its row.
+    pub chain_belts: bool,
+}
+
+fn is_belt(b: &Building) -> bool {
+    b.kind().map(|k| k.is_belt()).unwrap_or(false)
+}
+
+/// Pairs of (belt end, belt start) positions to connect between consecutive copies.
+///
+/// An end is linked to the start of the next copy that sits right next to it, i.e. within
+/// one and a half grid cells after shifting by the spacing.
+fn belt_chain_pairs(buildings: &[Building], dx: f32, dy: f32) -> Vec<(usize, usize)> {
+    let fed: Vec<u32> = buildings
+        .iter()
+        .filter(|b| is_belt(b))
+        .map(|b| b.header.output_object_index)
+        .collect();
+    let pos = |b: &Building| (b.header.local_offset_x, b.header.local_offset_y);
+    let ends = buildings
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| is_belt(b) && b.header.output_object_index == NO_OBJECT);
+    let starts: Vec<(usize, (f32, f32))> = buildings
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| is_belt(b) && !fed.contains(&(b.header.index as u32)))
+        .map(|(i, b)| (i, pos(b)))
+        .collect();
+    ends.filter_map(|(e, b)| {
+        let (ex, ey) = pos(b);
+        starts
+            .iter()
+            .map(|(s, (sx, sy))| (*s, (sx + dx - ex).hypot(sy + dy - ey)))
+            .filter(|(_, d)| *d <= 1.5)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(s, _)| (e, s))
+    })
+    .collect()
+}
+
+/// Replicates a blueprint's buildings `columns` × `rows` times.
+///
+/// Buildings are renumbered copy by copy and connections are remapped to stay within each
+/// copy. Connections to buildings missing from the blueprint are cleared. Returns the number
+/// of belt links added between copies.
+pub fn tile(data: &mut BlueprintData, opts: &TileOptions) -> anyhow::Result<usize> {
+    if opts.columns == 0 || opts.rows == 0 {
+        return Err(some_error("Tile counts must be at least 1"));
+    }
+    if data.areas.len() != 1 {
+        return Err(some_error(format!(
+            "Can only tile blueprints with a single area, this one has {}",
+            data.areas.len()
+        )));
+    }
+    let dx = opts.spacing_x.unwrap_or(data.header.dragbox_size_x as f32);
+    let dy = opts.spacing_y.unwrap_or(data.header.dragbox_size_y as f32);
+    if !(dx >= 0.0 && dy >= 0.0 && dx.is_finite() && dy.is_finite()) {
+        return Err(some_error(format!(
+            "Tile spacing must not be negative, got {} by {}",
+            dx, dy
+        )));
+    }
+    let n = data.buildings.len();
+    let copies = opts.columns as u64 * opts.rows as u64;
+    if (n as u64)
+        .checked_mul(copies)
+        .filter(|total| *total <= i32::MAX as u64)
+        .is_none()
+    {
+        return Err(some_error("Too many buildings"));
+    }
+    let copies = copies as usize;
+
+    let position: HashMap<u32, usize> = data
+        .buildings
+        .iter()
+        .enumerate()
+        .map(|(p, b)| (b.header.index as u32, p))
+        .collect();
+    let remap = |link: u32, base: usize| match position.get(&link) {
+        Some(p) => (base + p) as u32,
+        None => NO_OBJECT,
+    };
+    let chain = match opts.chain_belts {
+        true if opts.columns > 1 => belt_chain_pairs(&data.buildings, dx, 0.0),
+        true => belt_chain_pairs(&data.buildings, 0.0, dy),
+        false => vec![],
+    };
+
+    let mut out = Vec::with_capacity(n * copies);
+    for row in 0..opts.rows {
+        for col in 0..opts.columns {
+            let base = out.len();
+            let (ox, oy) = (col as f32 * dx, row as f32 * dy);
+            for (p, b) in data.buildings.iter().enumerate() {
+                let mut b = b.clone();
+                let h = &mut b.header;
+                h.index = (base + p) as i32;
+                h.local_offset_x += ox;
+                h.local_offset_x2 += ox;
+                h.local_offset_y += oy;
+                h.local_offset_y2 += oy;
+                h.output_object_index = remap(h.output_object_index, base);
+                h.input_object_index = remap(h.input_object_index, base);
+                out.push(b);
+            }
+        }
+    }
+
+    let mut linked = 0;
+    for copy in 0..copies {
+        let next = match opts.columns {
+            1 => copy + 1,
+            c if (copy + 1) % c as usize != 0 => copy + 1,
+            _ => continue,
+        };
+        if next >= copies {
+            continue;
+        }
+        for (e, s) in chain.iter() {
+            let h = &mut out[copy * n + e].header;
+            h.output_object_index = (next * n + s) as u32;
+            h.output_to_slot = 1;
+            linked += 1;
+        }
+    }
+
+    let grow_x = ((opts.columns - 1) as f32 * dx).ceil().max(0.0) as u32;
+    let grow_y = ((opts.rows - 1) as f32 * dy).ceil().max(0.0) as u32;
+    data.header.dragbox_size_x += grow_x;
+    data.header.dragbox_size_y += grow_y;
+    let area = &mut data.areas[0];
+    area.width = area
+        .width
+        .saturating_add(grow_x.min(u16::MAX as u32) as u16);
+    area.height = area
+        .height
+        .saturating_add(grow_y.min(u16::MAX as u32) as u16);
+    data.building_count = out.len() as u32;
+    data.buildings = out;
+    Ok(linked)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{tile, TileOptions};
+    use crate::{
+        blueprint::Blueprint,
+        builder::BlueprintBuilder,
+        data::{building::NO_OBJECT, enums::DSPItem},
+        testutil::get_file,
+    };
+
+    #[test]
+    fn tile_example_renumbers_and_remaps() {
+        let f = get_file("旧版本5飞机.txt");
+        let mut bp = Blueprint::new(std::str::from_utf8(&f).unwrap().trim()).unwrap();
+        let n = bp.data.buildings.len();
+        let width = bp.data.areas[0].width;
+        tile(
+            &mut bp.data,
+            &TileOptions {
+                columns: 3,
+                rows: 2,
+                spacing_x: None,
+                spacing_y: Some(30.0),
+                chain_belts: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(bp.data.buildings.len(), n * 6);
+        assert_eq!(bp.data.building_count as usize, n * 6);
+        assert_eq!(bp.data.areas[0].width, width * 3);
+        for (i, b) in bp.data.buildings.iter().enumerate() {
+            assert_eq!(b.header.index, i as i32);
+            let copy = i / n;
+            for link in [b.header.output_object_index, b.header.input_object_index] {
+                assert!(link == NO_OBJECT || link as usize / n == copy);
+            }
+        }
+        let s = bp.into_bp_string(6).unwrap();
+        assert_eq!(Blueprint::new(&s).unwrap().data.buildings.len(), n * 6);
+    }
+
+    #[test]
+    fn tile_chains_belts() {
+        let mut b = BlueprintBuilder::new();
+        b.belt_path(DSPItem::ConveyorBeltMKI, &[(0, 0), (3, 0)])
+            .unwrap();
+        let mut bp = b.build();
+        let linked = tile(
+            &mut bp.data,
+            &TileOptions {
+                columns: 3,
+                rows: 1,
+                spacing_x: Some(4.0),
+                spacing_y: None,
+                chain_belts: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(linked, 2);
+        let ends: Vec<_> = bp
+            .data
+            .buildings
+            .iter()
+            .filter(|b| b.header.output_object_index == NO_OBJECT)
+            .collect();
+        assert_eq!(ends.len(), 1);
+        assert_eq!(bp.data.buildings[3].header.output_object_index, 4);
+    }
+
+    #[test]
+    fn tile_rejects_bad_options() {
+        let mut b = BlueprintBuilder::new();
+        b.building(DSPItem::TeslaTower, 0.0, 0.0, 0.0).unwrap();
+        let mut bp = b.build();
+        for (columns, rows, spacing_x) in [(70_000, 70_000, None), (2, 1, Some(-3.0))] {
+            let opts = TileOptions {
+                columns,
+                rows,
+                spacing_x,
+                spacing_y: None,
+                chain_belts: false,
+            };
+            assert!(tile(&mut bp.data, &opts).is_err());
+        }
+        assert_eq!(bp.data.buildings.len(), 1);
+    }
+}
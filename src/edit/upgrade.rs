@@ -0,0 +1,207 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    data::{
+        blueprint::BlueprintData,
+        enums::{BPModel, DSPItem},
+        families::{family, family_of},
+    },
+    error::some_error,
+};
+
+use super::select::Selector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+/// Moves buildings up or down their upgrade family.
+#[derive(Debug, Clone)]
+pub struct TierChange {
+    pub direction: Direction,
+    /// Target tier, counted from 1 within each family and capped at the family size. `None`
+    /// means the highest tier when upgrading and the lowest when downgrading. Buildings are
+    /// never moved in the other direction.
+    pub tier: Option<usize>,
+    /// Ids of the families to change. Empty changes all of them.
+    pub families: Vec<String>,
+}
+
+impl TierChange {
+    pub fn upgrade_all(tier: Option<usize>) -> Self {
+        Self {
+            direction: Direction::Up,
+            tier,
+            families: vec![],
+        }
+    }
+
+    pub fn downgrade_all(tier: Option<usize>) -> Self {
+        Self {
+            direction: Direction::Down,
+            tier,
+            families: vec![],
+        }
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.tier == Some(0) {
+            return Err(some_error("Tiers are counted from 1"));
+        }
+        for f in self.families.iter() {
+            match family(f) {
+                None => return Err(some_error(format!("Unknown upgrade family \"{}\"", f))),
+                Some(f) if !f.is_available() => {
+                    return Err(some_error(format!(
+                        "Upgrade family \"{}\" has no building models yet",
+                        f.id
+                    )))
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// The building `i` gets replaced with, if any. Families that are not available are left
+    /// alone.
+    pub fn target(&self, i: DSPItem) -> Option<DSPItem> {
+        let f = family_of(i).filter(|f| f.is_available())?;
+        if !self.families.is_empty() && !self.families.iter().any(|id| id == f.id) {
+            return None;
+        }
+        let current = f.tier_of(i)?;
+        let last = f.tiers.len() - 1;
+        let wanted = self.tier.map(|t| (t - 1).min(last));
+        let to = match self.direction {
+            Direction::Up => current.max(wanted.unwrap_or(last)),
+            Direction::Down => current.min(wanted.unwrap_or(0)),
+        };
+        (to != current).then(|| f.tiers[to])
+    }
+}
+
+/// What a tier change did, as (from, to, count) sorted by item ID.
+#[derive(Debug, Default)]
+pub struct UpgradeReport {
+    pub changed: Vec<(DSPItem, DSPItem, usize)>,
+    /// Buildings left alone because the replacement's model index is not known.
+    pub unmapped: Vec<(DSPItem, DSPItem, usize)>,
+}
+
+impl UpgradeReport {
+    pub fn changed_count(&self) -> usize {
+        self.changed.iter().map(|c| c.2).sum()
+    }
+}
+
+impl Display for UpgradeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (from, to, n) in self.changed.iter() {
+            writeln!(f, "{} -> {}: {}", from.as_ref(), to.as_ref(), n)?;
+        }
+        for (from, to, n) in self.unmapped.iter() {
+            writeln!(
+                f,
+                "Not changed, no model for {}: {} -> {}: {}",
+                to.as_ref(),
+                from.as_ref(),
+                to.as_ref(),
+                n
+            )?;
+        }
+        writeln!(f, "Changed {} buildings.", self.changed_count())
+    }
+}
+
+fn sorted(m: HashMap<(DSPItem, DSPItem), usize>) -> Vec<(DSPItem, DSPItem, usize)> {
+    let mut v: Vec<_> = m.into_iter().map(|((a, b), n)| (a, b, n)).collect();
+    v.sort_by_key(|(a, b, _)| (u16::from(*a), u16::from(*b)));
+    v
+}
+
+/// Works out the building replacements for a tier change, without changing anything.
+pub fn plan(
+    data: &BlueprintData,
+    change: &TierChange,
+    selector: Option<&Selector>,
+) -> (HashMap<DSPItem, DSPItem>, UpgradeReport) {
+    let mut map = HashMap::new();
+    let mut changed = HashMap::new();
+    let mut unmapped = HashMap::new();
+    for b in data.buildings.iter() {
+        if selector.is_some_and(|s| !s.matches(b)) {
+            continue;
+        }
+        let Ok(from) = DSPItem::try_from(b.header.item_id) else {
+            continue;
+        };
+        let Some(to) = change.target(from) else {
+            continue;
+        };
+        if BPModel::from_building(to).is_ok() {
+            map.insert(from, to);
+            *changed.entry((from, to)).or_insert(0) += 1;
+        } else {
+            *unmapped.entry((from, to)).or_insert(0) += 1;
+        }
+    }
+    let report = UpgradeReport {
+        changed: sorted(changed),
+        unmapped: sorted(unmapped),
+    };
+    (map, report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{plan, TierChange};
+    use crate::{builder::BlueprintBuilder, data::enums::DSPItem};
+
+    #[test]
+    fn tiers_move_within_families() {
+        let up = TierChange::upgrade_all(None);
+        assert_eq!(
+            up.target(DSPItem::ConveyorBeltMKI),
+            Some(DSPItem::ConveyorBeltMKIII)
+        );
+        assert_eq!(up.target(DSPItem::ConveyorBeltMKIII), None);
+        assert_eq!(up.target(DSPItem::IronOre), None);
+        let up2 = TierChange::upgrade_all(Some(2));
+        assert_eq!(up2.target(DSPItem::SorterMKI), Some(DSPItem::SorterMKII));
+        assert_eq!(up2.target(DSPItem::SorterMKIII), None);
+        let down = TierChange::downgrade_all(None);
+        assert_eq!(
+            down.target(DSPItem::PlaneSmelter),
+            Some(DSPItem::ArcSmelter)
+        );
+
+        let mut b = BlueprintBuilder::new();
+        b.belt_path(DSPItem::ConveyorBeltMKI, &[(0, 0), (3, 0)])
+            .unwrap();
+        b.building(DSPItem::SelfevolutionLab, 0.0, 2.0, 0.0)
+            .unwrap();
+        let data = b.build().data;
+        let (map, report) = plan(&data, &up, None);
+        assert_eq!(map.len(), 1);
+        assert_eq!(
+            report.changed,
+            vec![(DSPItem::ConveyorBeltMKI, DSPItem::ConveyorBeltMKIII, 4)]
+        );
+        assert!(report.unmapped.is_empty());
+        // No model index is known for the matrix lab, so labs are not an available family.
+        let (map, report) = plan(&data, &down, None);
+        assert!(map.is_empty() && report.unmapped.is_empty());
+        let mut labs = TierChange::downgrade_all(None);
+        labs.families = vec!["lab".to_owned()];
+        assert!(labs.check().is_err());
+
+        let mut belts_only = TierChange::upgrade_all(None);
+        belts_only.families = vec!["belt".to_owned()];
+        assert_eq!(plan(&data, &belts_only, None).0.len(), 1);
+        belts_only.families = vec!["bolt".to_owned()];
+        assert!(belts_only.check().is_err());
+    }
+}
@@ -1,7 +1,70 @@
+use std::fmt::Display;
+
+use crate::data::{enums::DSPItem, traits::ItemId};
+
+/// Identifies a building within a blueprint's building list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuildingContext {
+    /// Position in the building list.
+    pub position: usize,
+    /// Stored building index and item, if the building header could be read.
+    pub index: Option<i32>,
+    pub item: Option<ItemId<u16>>,
+}
+
+impl Display for BuildingContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "building #{}", self.position)?;
+        if let Some(index) = self.index {
+            write!(f, " (index {}", index)?;
+            match self.item.map(DSPItem::try_from) {
+                Some(Ok(i)) => write!(f, ", {})", i.as_ref())?,
+                Some(Err(_)) => write!(f, ", unknown item {})", self.item.unwrap().0)?,
+                None => write!(f, ")")?,
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("{0}")]
     E(String),
+    #[error("Blueprint hash does not match calculated hash: {expected} != {actual}")]
+    HashMismatch { expected: String, actual: String },
+    #[error("Invalid blueprint hash: {0}")]
+    BadHash(String),
+    #[error("Unexpected prefix: {0:?}")]
+    BadPrefix(String),
+    #[error("Failed to parse blueprint header field {field}: {value:?}")]
+    Csv { field: &'static str, value: String },
+    #[error("Failed to base64 decode blueprint: {0}")]
+    Base64(#[source] base64::DecodeError),
+    #[error("Failed to decompress blueprint: {0}")]
+    Gzip(#[source] std::io::Error),
+    #[error("Failed to decode blueprint data at byte offset {offset}{}: {message}", building.map(|b| format!(" in {}", b)).unwrap_or_default())]
+    Decode {
+        offset: u64,
+        building: Option<BuildingContext>,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Short machine-readable name of the error variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::E(_) => "other",
+            Self::HashMismatch { .. } => "hash_mismatch",
+            Self::BadHash(_) => "bad_hash",
+            Self::BadPrefix(_) => "bad_prefix",
+            Self::Csv { .. } => "csv",
+            Self::Base64(_) => "base64",
+            Self::Gzip(_) => "gzip",
+            Self::Decode { .. } => "decode",
+        }
+    }
 }
 
 impl<T: Into<String>> From<T> for Error {
@@ -1,13 +1,19 @@
-use args::Commands;
-use blueprint::Blueprint;
+use args::{AreaCommands, Commands, GenCommands, MetaCommands};
+use blueprint::{Blueprint, ParseOptions};
+use builder::BlueprintBuilder;
 use clap::Parser;
 use data::{
-    enums::{DSPItem, DSPRecipe},
+    enums::{DSPIcon, DSPItem, DSPRecipe, DSPSignal, DSPTech},
+    families::FAMILIES,
     traits::{DSPEnum, TryFromUserString},
 };
-use edit::EditBlueprint;
+use edit::{
+    tile::TileOptions,
+    upgrade::{Direction, TierChange},
+    EditBlueprint,
+};
 use error::some_error;
-use locale::{Locale, GLOBAL_SERIALIZATION_LOCALE};
+use locale::{Locale, LocalizedEnum};
 use std::{
     collections::HashMap,
     fs::File,
@@ -18,16 +24,25 @@ use strum::IntoEnumIterator;
 use crate::{data::visit::Visitor, edit::stats::GetStats};
 
 pub(crate) mod args;
-pub(crate) mod blueprint;
-pub(crate) mod data;
-pub(crate) mod edit;
-pub(crate) mod error;
+pub(crate) mod batch;
+pub mod blueprint;
+pub mod builder;
+pub mod coords;
+pub mod data;
+#[cfg(feature = "dump")]
+pub mod dump;
+pub mod edit;
+pub mod error;
 pub(crate) mod locale;
-pub(crate) mod md5;
+pub mod md5;
 pub(crate) mod version;
 #[cfg(feature = "python")]
 pub(crate) mod python;
+#[cfg(feature = "script")]
+pub mod script;
 pub(crate) mod stats;
+pub mod timestamp;
+pub(crate) mod verify;
 #[cfg(feature = "wasm")]
 pub mod wasm;
 #[cfg(test)]
@@ -85,13 +100,6 @@ impl Seek for WriteSeek {
     }
 }
 
-fn itob(i: &mut Box<dyn ReadPlusSeek>) -> anyhow::Result<Blueprint> {
-    let mut data = vec![];
-    i.read_to_end(&mut data)?;
-    let data = String::from_utf8(data)?;
-    Blueprint::new(&data)
-}
-
 fn parse_comma_list(s: &str) -> anyhow::Result<Vec<(String, String)>> {
     s.split(",")
         .map(|v| {
@@ -104,6 +112,40 @@ fn parse_comma_list(s: &str) -> anyhow::Result<Vec<(String, String)>> {
         .collect()
 }
 
+fn parse_waypoint(s: &str) -> anyhow::Result<(i32, i32)> {
+    let err = || {
+        some_error(format!(
+            "Invalid waypoint: \"{}\". Expected two integers separated by a comma, like \"10,-5\".",
+            s
+        ))
+    };
+    let (x, y) = s.split_once(',').ok_or_else(err)?;
+    Ok((
+        x.trim().parse().map_err(|_| err())?,
+        y.trim().parse().map_err(|_| err())?,
+    ))
+}
+
+/// Parses "SLOT=ICON" with a slot from 1 to 5. An empty icon clears the slot.
+fn parse_icon_arg(s: &str) -> anyhow::Result<(usize, Option<DSPIcon>)> {
+    let err = || {
+        some_error(format!(
+            "Invalid icon: \"{}\". Expected a slot from 1 to 5 and an icon, like \"1=IronIngot\".",
+            s
+        ))
+    };
+    let (slot, icon) = s.split_once('=').ok_or_else(err)?;
+    let slot: usize = slot.trim().parse().map_err(|_| err())?;
+    if !(1..=5).contains(&slot) {
+        return Err(err());
+    }
+    let icon = match icon.trim() {
+        "" => None,
+        icon => Some(DSPIcon::try_from_user_string(icon)?),
+    };
+    Ok((slot - 1, icon))
+}
+
 fn parse_into_enum_map<T: DSPEnum + 'static>(s: &str) -> anyhow::Result<HashMap<T, T>> {
     let l = parse_comma_list(s)?;
     let mut map = HashMap::new();
@@ -118,72 +160,129 @@ fn parse_into_enum_map<T: DSPEnum + 'static>(s: &str) -> anyhow::Result<HashMap<
     Ok(map)
 }
 
-pub fn cmdline() -> anyhow::Result<()> {
-    let args = args::Args::parse();
+/// What a command produced from one input.
+#[derive(Default)]
+pub(crate) struct Processed {
+    /// New contents for the output file, if the command writes one.
+    pub output: Option<Vec<u8>>,
+    /// Text for standard output.
+    pub report: String,
+    /// Warnings and progress messages for standard error.
+    pub notes: Vec<String>,
+    /// A problem found after the report was made. The report is still shown, then the
+    /// command fails.
+    pub problem: Option<String>,
+}
 
-    let input = || -> anyhow::Result<Box<dyn ReadPlusSeek>> {
-        match iof(&args.input) {
-            None => {
-                let mut all_input = vec![];
-                eprintln!("Reading blueprint from standard input.");
-                std::io::stdin().read_to_end(&mut all_input)?;
-                Ok(Box::new(Cursor::new(all_input)))
-            }
-            Some(file) => Ok(Box::new(std::fs::File::open(file)?)),
+impl Processed {
+    fn output(data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            output: Some(data.into()),
+            ..Default::default()
         }
-    };
-    let output = || -> anyhow::Result<WriteSeek> {
-        match iof(&args.output) {
-            None => Ok(WriteSeek::BufOut(Cursor::new(vec![]), std::io::stdout())),
-            Some(file) => Ok(WriteSeek::File(
-                std::fs::OpenOptions::new()
-                    .write(true)
-                    .truncate(true)
-                    .create(true)
-                    .open(file)?,
-            )),
+    }
+
+    fn report(report: String) -> Self {
+        Self {
+            report,
+            ..Default::default()
         }
-    };
+    }
+}
 
-    match args.command {
+/// Whether a command reads the input blueprint. Those that do can also run in batch mode.
+pub(crate) fn reads_input(command: &Commands) -> bool {
+    match command {
+        Commands::Items(_)
+        | Commands::Recipes(_)
+        | Commands::Signals(_)
+        | Commands::Techs(_)
+        | Commands::Families
+        | Commands::Gen(_) => false,
         #[cfg(feature = "dump")]
-        Commands::Dump(args) => {
-            if args.human_readable {
-                let locale = match &args.locale {
-                    None => Locale::en,
-                    Some(s) => Locale::try_from_user_string(s)?,
-                };
-                let _ = GLOBAL_SERIALIZATION_LOCALE.set(locale);
-            }
-            let mut input = input()?;
-            let mut output = output()?;
-            let bp = itob(&mut input)?;
-            output.write_all(&bp.dump_json()?)?;
-            output.flush_if_stdout()?;
+        Commands::Schema => false,
+        Commands::Verify(vargs) => vargs.dir.is_none(),
+        Commands::Meta(MetaCommands::List(_)) => false,
+        #[cfg(feature = "script")]
+        Commands::Apply(sargs) => sargs.inputs.is_empty(),
+        _ => true,
+    }
+}
+
+/// Whether a command writes an output file, as opposed to only printing a report.
+pub(crate) fn writes_output(command: &Commands) -> bool {
+    match command {
+        Commands::Info
+        | Commands::CheckHash
+        | Commands::Lint
+        | Commands::Latitude(_)
+        | Commands::Verify(_) => false,
+        Commands::Areas(aargs) => aargs.command.is_some(),
+        Commands::Meta(margs) => matches!(margs, MetaCommands::Set(_)),
+        _ => true,
+    }
+}
+
+fn parse(data: &[u8]) -> anyhow::Result<Blueprint> {
+    Blueprint::new(std::str::from_utf8(data)?)
+}
+
+/// Locale of human readable dumps, en unless given.
+#[cfg(feature = "dump")]
+fn dump_locale(dargs: &args::DumpArgs) -> anyhow::Result<Locale> {
+    match &dargs.locale {
+        None => Ok(Locale::en),
+        Some(s) => Locale::try_from_user_string(s),
+    }
+}
+
+/// Runs a command that reads an input blueprint on the contents of one input.
+pub(crate) fn process(
+    command: &Commands,
+    compression_level: u32,
+    data: &[u8],
+) -> anyhow::Result<Processed> {
+    Ok(match command {
+        #[cfg(feature = "dump")]
+        Commands::Dump(dargs) => {
+            let bp = parse(data)?;
+            Processed::output(match dargs.human_readable {
+                true => bp.dump_json_in(dump_locale(dargs)?)?,
+                false => bp.dump_json()?,
+            })
         }
         #[cfg(feature = "dump")]
         Commands::Undump => {
-            let mut data = vec![];
-            let mut input = input()?;
-            let mut output = output()?;
-            input.read_to_end(&mut data)?;
-            let data = String::from_utf8(data)?;
-            let bp = Blueprint::new_from_json(&data)?;
-            output.write_all(bp.into_bp_string(args.compression_level)?.as_bytes())?;
-            output.flush_if_stdout()?;
+            let bp = Blueprint::new_from_json(std::str::from_utf8(data)?)?;
+            Processed::output(bp.into_bp_string(compression_level)?)
+        }
+        #[cfg(feature = "script")]
+        Commands::Apply(sargs) => {
+            let text = std::fs::read_to_string(&sargs.script)?;
+            let script = script::Script::from_toml(&text)?;
+            if !script.inputs.is_empty() {
+                return Err(some_error(
+                    "The script lists its own inputs and cannot edit the input file",
+                ));
+            }
+            Processed::output(script.apply_str(std::str::from_utf8(data)?)?)
         }
         Commands::Edit(eargs) => {
-            let mut input = input()?;
-            let mut output = output()?;
-            let mut bp = EditBlueprint::new(itob(&mut input)?);
+            let mut bp = EditBlueprint::new(parse(data)?);
+            let selector = eargs
+                .selector
+                .as_ref()
+                .map(|s| s.parse::<edit::select::Selector>())
+                .transpose()?;
+            let selector = selector.as_ref();
 
             let mut item_replace = HashMap::new();
             let mut recipe_replace = HashMap::new();
             let mut building_replace = HashMap::new();
 
             // This goes first so it can be overwritten.
-            if let Some(i) = eargs.replace_both {
-                let mut r = parse_into_enum_map::<DSPItem>(&i)?;
+            if let Some(i) = &eargs.replace_both {
+                let mut r = parse_into_enum_map::<DSPItem>(i)?;
                 let mut r2: HashMap<DSPRecipe, DSPRecipe> = r
                     .iter()
                     .filter_map(|(k, v)| {
@@ -195,54 +294,470 @@ pub fn cmdline() -> anyhow::Result<()> {
                 item_replace.extend(r.drain());
                 recipe_replace.extend(r2.drain());
             }
-            if let Some(i) = eargs.replace_item {
-                let mut r = parse_into_enum_map::<DSPItem>(&i)?;
+            if let Some(i) = &eargs.replace_item {
+                let mut r = parse_into_enum_map::<DSPItem>(i)?;
                 item_replace.extend(r.drain());
             }
-            if let Some(i) = eargs.replace_recipe {
-                let mut r = parse_into_enum_map::<DSPRecipe>(&i)?;
+            if let Some(i) = &eargs.replace_recipe {
+                let mut r = parse_into_enum_map::<DSPRecipe>(i)?;
                 recipe_replace.extend(r.drain());
             }
 
-            if let Some(i) = eargs.replace_building {
-                building_replace = parse_into_enum_map::<DSPItem>(&i)?;
+            if let Some(i) = &eargs.replace_building {
+                building_replace = parse_into_enum_map::<DSPItem>(i)?;
             }
 
             if !item_replace.is_empty() {
-                bp.replace_item(item_replace);
+                bp.replace_item_where(item_replace, selector);
             }
             if !recipe_replace.is_empty() {
-                bp.replace_recipe(recipe_replace);
+                bp.replace_recipe_where(recipe_replace, selector);
             }
 
             if !building_replace.is_empty() {
-                bp.replace_building(building_replace)?;
+                bp.replace_building_where(building_replace, selector)?;
             }
 
-            if let Some(i) = eargs.icon_text {
-                bp.set_icon_text(&i);
+            let mut notes = vec![];
+            if let Some(m) = &eargs.proliferator_mode {
+                let changed = bp.set_proliferator_mode_where(m.parse()?, selector);
+                notes.push(format!(
+                    "Changed the proliferator mode of {} buildings.",
+                    changed
+                ));
+            }
+
+            if let Some(i) = &eargs.icon_text {
+                bp.set_icon_text(i);
+            }
+            if let Some(d) = &eargs.description {
+                bp.set_description(&d.replace("\\n", "\n"));
+            }
+            if let Some(t) = &eargs.timestamp {
+                bp.set_timestamp(t)?;
+            }
+            if let Some(l) = eargs.layout {
+                bp.set_layout(l);
+            }
+            if eargs.auto_icon {
+                match bp.auto_icon() {
+                    Some(icon) => notes.push(format!("Set the first icon to {}.", icon)),
+                    None => notes.push("No building has a recipe, icons left alone.".to_owned()),
+                }
+            }
+            for i in eargs.icons.iter() {
+                let (slot, icon) = parse_icon_arg(i)?;
+                bp.set_icon(slot, icon)?;
+            }
+            Processed {
+                notes,
+                ..Processed::output(bp.0.into_bp_string(compression_level)?)
             }
-            output.write_all(bp.0.into_bp_string(args.compression_level)?.as_bytes())?;
-            output.flush_if_stdout()?;
         }
         Commands::Info => {
-            let mut input = input()?;
-            let mut bp = itob(&mut input)?;
-            println!("{}", bp.get_description()?);
+            let mut bp = parse(data)?;
             let mut stats = GetStats::new();
             stats.visit_blueprint(&mut bp);
-            print!("{}", stats.0);
+            let icons: Vec<String> = bp
+                .icons
+                .map(edit::icon::decode_icon)
+                .iter()
+                .map(|i| i.map_or("-".to_owned(), |i| i.to_string()))
+                .collect();
+            Processed::report(format!(
+                "{}\nSaved: {}\nIcons: {}\n{}",
+                bp.get_description()?,
+                bp.get_timestamp(),
+                icons.join(", "),
+                stats.0
+            ))
+        }
+        Commands::Lint => {
+            let bp = parse(data)?;
+            let problems = edit::lint::lint(&bp.data);
+            let mut report = String::new();
+            for p in problems.iter() {
+                report += &format!("Problem: {}\n", p);
+            }
+            if problems.is_empty() {
+                report += "No problems found.\n";
+            }
+            Processed {
+                problem: (!problems.is_empty()).then(|| format!("{} problems", problems.len())),
+                ..Processed::report(report)
+            }
+        }
+        Commands::Repair => {
+            let data = String::from_utf8_lossy(data);
+            let (bp, warnings) = Blueprint::new_with_options(&data, &ParseOptions::repair())?;
+            let mut notes: Vec<String> =
+                warnings.iter().map(|w| format!("Warning: {}", w)).collect();
+            if notes.is_empty() {
+                notes.push("Blueprint is not damaged.".to_owned());
+            }
+            Processed {
+                notes,
+                ..Processed::output(bp.into_bp_string(compression_level)?)
+            }
+        }
+        Commands::Sign => Processed::output(Blueprint::sign(std::str::from_utf8(data)?)?),
+        Commands::CheckHash => {
+            Blueprint::check_hash(std::str::from_utf8(data)?)?;
+            Processed::report("Hash OK.\n".to_owned())
         }
-        Commands::Items => {
-            for e in DSPItem::iter() {
-                println!("{}", e.as_ref())
+        Commands::Tile(targs) => {
+            let mut bp = EditBlueprint::new(parse(data)?);
+            let linked = bp.tile(&TileOptions {
+                columns: targs.columns,
+                rows: targs.rows,
+                spacing_x: targs.dx,
+                spacing_y: targs.dy,
+                chain_belts: targs.chain_belts,
+            })?;
+            let mut p = Processed::output(bp.0.into_bp_string(compression_level)?);
+            if targs.chain_belts {
+                p.notes
+                    .push(format!("Linked {} belts between copies.", linked));
             }
+            p
         }
-        Commands::Recipes => {
-            for e in DSPRecipe::iter() {
-                println!("{}", e.as_ref())
+        Commands::Areas(aargs) => {
+            let mut bp = EditBlueprint::new(parse(data)?);
+            let note = match &aargs.command {
+                None => {
+                    let mut report = String::new();
+                    for a in bp.areas() {
+                        report += &format!("{}\n", a);
+                    }
+                    let problems = edit::area::area_problems(&bp.0.data);
+                    for p in problems.iter() {
+                        report += &format!("Problem: {}\n", p);
+                    }
+                    return Ok(Processed {
+                        problem: (!problems.is_empty())
+                            .then(|| format!("{} area problems", problems.len())),
+                        ..Processed::report(report)
+                    });
+                }
+                Some(AreaCommands::Move(margs)) => {
+                    let mut positions: Vec<usize> = match margs.from {
+                        Some(from) => (0..bp.0.data.buildings.len())
+                            .filter(|i| bp.0.data.buildings[*i].header.area_index == from)
+                            .collect(),
+                        None => vec![],
+                    };
+                    if let Some(list) = &margs.buildings {
+                        for p in list.split(',') {
+                            positions.push(p.trim().parse().map_err(|_| {
+                                some_error(format!("Invalid building position: \"{}\"", p))
+                            })?);
+                        }
+                    }
+                    bp.move_to_area(&positions, margs.to)?;
+                    format!("Moved {} buildings to area {}.", positions.len(), margs.to)
+                }
+                Some(AreaCommands::Flatten) => {
+                    let removed = bp.flatten_areas()?;
+                    format!("Removed {} areas.", removed)
+                }
+            };
+            Processed {
+                notes: vec![note],
+                ..Processed::output(bp.0.into_bp_string(compression_level)?)
             }
         }
+        Commands::Latitude(largs) => {
+            let bp = parse(data)?;
+            let grid = coords::PlanetGrid::new(largs.segment)?;
+            Processed::report(coords::latitude_report(&bp.data, &grid).to_string())
+        }
+        Commands::Verify(_) => match verify::verify(std::str::from_utf8(data)?.trim())? {
+            None => Processed::report("OK: blueprint re-serializes identically.\n".to_owned()),
+            Some(m) => return Err(some_error(m.to_string())),
+        },
+        Commands::Meta(MetaCommands::Get(margs)) => {
+            let m = EditBlueprint::new(parse(data)?).metadata()?;
+            match &margs.key {
+                Some(k) => {
+                    let v = m
+                        .get(k)
+                        .ok_or_else(|| some_error(format!("No metadata field '{}'", k)))?;
+                    Processed::report(format!("{}\n", v))
+                }
+                None => Processed::report(
+                    m.fields()
+                        .iter()
+                        .map(|(k, v)| format!("{}: {}\n", k, v))
+                        .collect(),
+                ),
+            }
+        }
+        Commands::Meta(MetaCommands::Set(margs)) => {
+            let mut bp = EditBlueprint::new(parse(data)?);
+            for f in margs.fields.iter() {
+                let (k, v) = f
+                    .split_once('=')
+                    .ok_or_else(|| some_error(format!("Expected key=value, got '{}'", f)))?;
+                bp.set_metadata(k, Some(v))?;
+            }
+            Processed::output(bp.0.into_bp_string(compression_level)?)
+        }
+        Commands::Upgrade(targs) | Commands::Downgrade(targs) => {
+            let mut bp = EditBlueprint::new(parse(data)?);
+            let change = TierChange {
+                direction: match command {
+                    Commands::Upgrade(_) => Direction::Up,
+                    _ => Direction::Down,
+                },
+                tier: targs.tier,
+                families: match &targs.families {
+                    Some(f) => f.split(',').map(|f| f.trim().to_owned()).collect(),
+                    None => vec![],
+                },
+            };
+            let selector = targs
+                .selector
+                .as_ref()
+                .map(|s| s.parse::<edit::select::Selector>())
+                .transpose()?;
+            let report = bp.change_tiers(&change, selector.as_ref())?;
+            Processed {
+                notes: report.to_string().lines().map(|l| l.to_owned()).collect(),
+                ..Processed::output(bp.0.into_bp_string(compression_level)?)
+            }
+        }
+        Commands::Items(_)
+        | Commands::Recipes(_)
+        | Commands::Signals(_)
+        | Commands::Techs(_)
+        | Commands::Families
+        | Commands::Gen(_)
+        | Commands::Meta(MetaCommands::List(_)) => {
+            return Err(some_error("This command does not read an input blueprint"))
+        }
+        #[cfg(feature = "dump")]
+        Commands::Schema => {
+            return Err(some_error("This command does not read an input blueprint"))
+        }
+    })
+}
+
+/// Prints enum names, one per line, followed by their names in a locale if one is given.
+fn print_names<T: LocalizedEnum + AsRef<str> + 'static>(
+    values: impl Iterator<Item = T>,
+    nargs: &args::NamesArgs,
+) -> anyhow::Result<()> {
+    let locale = nargs
+        .locale
+        .as_deref()
+        .map(Locale::try_from_user_string)
+        .transpose()?;
+    for e in values {
+        match locale.and_then(|l| locale::name_in(&e, l)) {
+            Some(name) => println!("{}\t{}", e.as_ref(), name),
+            None => println!("{}", e.as_ref()),
+        }
+    }
+    Ok(())
+}
+
+pub fn cmdline() -> anyhow::Result<()> {
+    let args = args::Args::parse();
+
+    // Fail on a bad locale before reading any input.
+    #[cfg(feature = "dump")]
+    if let Commands::Dump(dargs) = &args.command {
+        dump_locale(dargs)?;
+    }
+
+    if let Some(dir) = iof(&args.input).map(std::path::Path::new) {
+        if dir.is_dir() && reads_input(&args.command) {
+            return batch::run(
+                &args.command,
+                args.compression_level,
+                dir,
+                iof(&args.output).map(std::path::Path::new),
+                args.jobs,
+            );
+        }
+    }
+
+    let input = || -> anyhow::Result<Box<dyn ReadPlusSeek>> {
+        match iof(&args.input) {
+            None => {
+                let mut all_input = vec![];
+                eprintln!("Reading blueprint from standard input.");
+                std::io::stdin().read_to_end(&mut all_input)?;
+                Ok(Box::new(Cursor::new(all_input)))
+            }
+            Some(file) => Ok(Box::new(std::fs::File::open(file)?)),
+        }
+    };
+    let output = || -> anyhow::Result<WriteSeek> {
+        match iof(&args.output) {
+            None => Ok(WriteSeek::BufOut(Cursor::new(vec![]), std::io::stdout())),
+            Some(file) => Ok(WriteSeek::File(
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .truncate(true)
+                    .create(true)
+                    .open(file)?,
+            )),
+        }
+    };
+
+    match &args.command {
+        #[cfg(feature = "script")]
+        Commands::Apply(sargs) if !sargs.inputs.is_empty() => {
+            let text = std::fs::read_to_string(&sargs.script)?;
+            let script = script::Script::from_toml(&text)?;
+            let base = std::path::Path::new(&sargs.script)
+                .parent()
+                .unwrap_or(std::path::Path::new("."));
+            // Paths given on the command line are relative to the working directory.
+            let inputs: Vec<String> = sargs
+                .inputs
+                .iter()
+                .map(|i| std::fs::canonicalize(i).map(|p| p.to_string_lossy().into_owned()))
+                .collect::<std::io::Result<_>>()?;
+            run_script(&script, base, &inputs)?;
+        }
+        #[cfg(feature = "script")]
+        Commands::Apply(sargs) => {
+            let text = std::fs::read_to_string(&sargs.script)?;
+            let script = script::Script::from_toml(&text)?;
+            if script.inputs.is_empty() {
+                let mut data = vec![];
+                input()?.read_to_end(&mut data)?;
+                let out = script.apply_str(&String::from_utf8(data)?)?;
+                let mut output = output()?;
+                output.write_all(out.as_bytes())?;
+                output.flush_if_stdout()?;
+            } else {
+                let base = std::path::Path::new(&sargs.script)
+                    .parent()
+                    .unwrap_or(std::path::Path::new("."));
+                run_script(&script, base, &[])?;
+            }
+        }
+        Commands::Items(nargs) => print_names(DSPItem::iter(), nargs)?,
+        Commands::Recipes(nargs) => print_names(DSPRecipe::iter(), nargs)?,
+        Commands::Signals(nargs) => print_names(DSPSignal::iter(), nargs)?,
+        Commands::Techs(nargs) => print_names(DSPTech::iter(), nargs)?,
+        #[cfg(feature = "dump")]
+        Commands::Schema => println!("{}", Blueprint::dump_schema()?),
+        Commands::Families => {
+            for f in FAMILIES.iter() {
+                let tiers: Vec<&str> = f.tiers.iter().map(|t| t.as_ref()).collect();
+                let note = match f.is_available() {
+                    true => "",
+                    false => " (not available: building models unknown)",
+                };
+                println!("{}: {}{}", f.id, tiers.join(", "), note);
+            }
+        }
+        Commands::Gen(GenCommands::Belt(gargs)) => {
+            let tier = DSPItem::try_from_user_string(&gargs.tier)?;
+            let waypoints = gargs
+                .waypoints
+                .iter()
+                .map(|w| parse_waypoint(w))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut builder = BlueprintBuilder::new();
+            let belts = builder.belt_path(tier, &waypoints)?;
+            eprintln!("Generated {} belts.", belts.len());
+            let mut bp = builder.build();
+            if let Some(t) = &gargs.icon_text {
+                bp.set_icon_text(t);
+            }
+            let mut output = output()?;
+            output.write_all(bp.into_bp_string(args.compression_level)?.as_bytes())?;
+            output.flush_if_stdout()?;
+        }
+        Commands::Verify(vargs) if vargs.dir.is_some() => {
+            let dir = vargs.dir.as_ref().unwrap();
+            let results = verify::verify_dir(std::path::Path::new(dir))?;
+            let mut failed = 0;
+            for (path, res) in results.iter() {
+                match res {
+                    Ok(None) => println!("OK: {}", path.display()),
+                    Ok(Some(m)) => {
+                        failed += 1;
+                        println!("FAIL: {}: {}", path.display(), m)
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        println!("FAIL: {}: {:#}", path.display(), e)
+                    }
+                }
+            }
+            println!("{} blueprints, {} failed.", results.len(), failed);
+            if failed != 0 {
+                return Err(some_error(format!(
+                    "{} blueprints failed verification",
+                    failed
+                )));
+            }
+        }
+        Commands::Meta(MetaCommands::List(largs)) => {
+            let results =
+                edit::meta::search(std::path::Path::new(&largs.dir), largs.tag.as_deref())?;
+            let mut found = 0;
+            for (path, res) in results.iter() {
+                match res {
+                    Ok(m) => {
+                        found += 1;
+                        println!("{}", path.display());
+                        for (k, v) in m.fields() {
+                            println!("  {}: {}", k, v);
+                        }
+                    }
+                    Err(e) => eprintln!("Skipped {}: {:#}", path.display(), e),
+                }
+            }
+            println!("{} blueprints.", found);
+        }
+        command => {
+            let mut data = vec![];
+            input()?.read_to_end(&mut data)?;
+            let p = process(command, args.compression_level, &data)?;
+            for n in p.notes.iter() {
+                eprintln!("{}", n);
+            }
+            print!("{}", p.report);
+            if let Some(o) = p.output {
+                let mut output = output()?;
+                output.write_all(&o)?;
+                output.flush_if_stdout()?;
+            }
+            if let Some(problem) = p.problem {
+                return Err(some_error(problem));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "script")]
+fn run_script(
+    script: &script::Script,
+    base: &std::path::Path,
+    inputs: &[String],
+) -> anyhow::Result<()> {
+    let results = script.run(base, inputs)?;
+    let mut failed = 0;
+    for (input, res) in results.iter() {
+        match res {
+            Ok(out) => println!("OK: {} -> {}", input.display(), out.display()),
+            Err(e) => {
+                failed += 1;
+                println!("FAIL: {}: {:#}", input.display(), e)
+            }
+        }
+    }
+    println!("{} blueprints, {} failed.", results.len(), failed);
+    if failed != 0 {
+        return Err(some_error(format!("{} blueprints failed", failed)));
     }
     Ok(())
 }
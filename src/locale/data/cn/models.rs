@@ -0,0 +1,24 @@
+&[
+    (BPModel::ConveyorBeltMKI, "传送带"),
+    (BPModel::ConveyorBeltMKII, "高速传送带"),
+    (BPModel::ConveyorBeltMKIII, "极速传送带"),
+    (BPModel::SorterMKI, "分拣器"),
+    (BPModel::SorterMKII, "高速分拣器"),
+    (BPModel::SorterMKIII, "极速分拣器"),
+    (BPModel::SorterMKIV, "集装分拣器"),
+    (BPModel::InterstellarLogisticsStation, "星际物流运输站"),
+    (BPModel::TeslaTower, "电力感应塔"),
+    (BPModel::SatelliteSubstation, "卫星配电站"),
+    (BPModel::ArcSmelter, "电弧熔炉"),
+    (BPModel::AssemblingMachineMkI, "制造台 Mk.I"),
+    (BPModel::AssemblingMachineMkII, "制造台 Mk.II"),
+    (BPModel::AssemblingMachineMkIII, "制造台 Mk.III"),
+    (BPModel::ChemicalPlant, "化工厂"),
+    (BPModel::MiniatureParticleCollider, "微型粒子对撞机"),
+    (BPModel::SprayCoater, "喷涂机"),
+    (BPModel::PlaneSmelter, "位面熔炉"),
+    (BPModel::QuantumChemicalPlant, "量子化工厂"),
+    (BPModel::RecomposingAssembler, "重组式制造台"),
+    (BPModel::NegentropySmelter, "负熵熔炉"),
+    (BPModel::SelfevolutionLab, "自演化研究站"),
+]
@@ -91,7 +91,6 @@
     (DSPRecipe::SorterMKIII, "极速分拣器"),
     (DSPRecipe::DepotMKII, "大型储物仓"),
     (DSPRecipe::ConveyorBeltMKIII, "极速传送带"),
-    (DSPRecipe::SorterMKIV, "集装分拣器"),
     (DSPRecipe::PlanetaryLogisticsStation, "行星内物流运输站"),
     (DSPRecipe::LogisticsDrone, "物流运输机"),
     (DSPRecipe::InterstellarLogisticsStation, "星际物流运输站"),
@@ -156,4 +155,5 @@
     (DSPRecipe::RecomposingAssembler, "重组式制造台"),
     (DSPRecipe::NegentropySmelter, "负熵熔炉"),
     (DSPRecipe::StrangeAnnihilationFuelRod, "奇异湮灭燃料棒"),
+    (DSPRecipe::SorterMKIV, "集装分拣器"),
 ]
@@ -0,0 +1,12 @@
+&[
+    (DSPSignal::Number0, "数字0"),
+    (DSPSignal::Number1, "数字1"),
+    (DSPSignal::Number2, "数字2"),
+    (DSPSignal::Number3, "数字3"),
+    (DSPSignal::Number4, "数字4"),
+    (DSPSignal::Number5, "数字5"),
+    (DSPSignal::Number6, "数字6"),
+    (DSPSignal::Number7, "数字7"),
+    (DSPSignal::Number8, "数字8"),
+    (DSPSignal::Number9, "数字9"),
+]
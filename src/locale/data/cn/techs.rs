@@ -0,0 +1,5 @@
+&[
+    (DSPTech::DysonSphereProgram, "戴森球计划"),
+    (DSPTech::Electromagnetism, "电磁学"),
+    (DSPTech::ElectromagneticMatrix, "电磁矩阵"),
+]
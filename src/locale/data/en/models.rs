@@ -0,0 +1,24 @@
+&[
+    (BPModel::ConveyorBeltMKI, "Conveyor Belt MK.I"),
+    (BPModel::ConveyorBeltMKII, "Conveyor Belt MK.II"),
+    (BPModel::ConveyorBeltMKIII, "Conveyor Belt MK.III"),
+    (BPModel::SorterMKI, "Sorter MK.I"),
+    (BPModel::SorterMKII, "Sorter MK.II"),
+    (BPModel::SorterMKIII, "Sorter MK.III"),
+    (BPModel::SorterMKIV, "Sorter MK.IV"),
+    (BPModel::InterstellarLogisticsStation, "Interstellar Logistics Station"),
+    (BPModel::TeslaTower, "Tesla Tower"),
+    (BPModel::SatelliteSubstation, "Satellite Substation"),
+    (BPModel::ArcSmelter, "Arc Smelter"),
+    (BPModel::AssemblingMachineMkI, "Assembling Machine Mk.I"),
+    (BPModel::AssemblingMachineMkII, "Assembling Machine Mk.II"),
+    (BPModel::AssemblingMachineMkIII, "Assembling Machine Mk.III"),
+    (BPModel::ChemicalPlant, "Chemical Plant"),
+    (BPModel::MiniatureParticleCollider, "Miniature Particle Collider"),
+    (BPModel::SprayCoater, "Spray Coater"),
+    (BPModel::PlaneSmelter, "Plane Smelter"),
+    (BPModel::QuantumChemicalPlant, "Quantum Chemical Plant"),
+    (BPModel::RecomposingAssembler, "Re-composing Assembler"),
+    (BPModel::NegentropySmelter, "Negentropy Smelter"),
+    (BPModel::SelfevolutionLab, "Self-evolution Lab"),
+]
@@ -91,7 +91,6 @@
     (DSPRecipe::SorterMKIII, "Sorter MK.III"),
     (DSPRecipe::DepotMKII, "Depot MK.II"),
     (DSPRecipe::ConveyorBeltMKIII, "Conveyor Belt MK.III"),
-    (DSPRecipe::SorterMKIV, "Sorter MK.IV"),
     (DSPRecipe::PlanetaryLogisticsStation, "Planetary Logistics Station"),
     (DSPRecipe::LogisticsDrone, "Logistics Drone"),
     (DSPRecipe::InterstellarLogisticsStation, "Interstellar Logistics Station"),
@@ -156,4 +155,5 @@
     (DSPRecipe::RecomposingAssembler, "Re-composing Assembler"),
     (DSPRecipe::NegentropySmelter, "Negentropy Smelter"),
     (DSPRecipe::StrangeAnnihilationFuelRod, "Strange Annihilation Fuel Rod"),
+    (DSPRecipe::SorterMKIV, "Sorter MK.IV"),
 ]
@@ -0,0 +1,12 @@
+&[
+    (DSPSignal::Number0, "Number 0"),
+    (DSPSignal::Number1, "Number 1"),
+    (DSPSignal::Number2, "Number 2"),
+    (DSPSignal::Number3, "Number 3"),
+    (DSPSignal::Number4, "Number 4"),
+    (DSPSignal::Number5, "Number 5"),
+    (DSPSignal::Number6, "Number 6"),
+    (DSPSignal::Number7, "Number 7"),
+    (DSPSignal::Number8, "Number 8"),
+    (DSPSignal::Number9, "Number 9"),
+]
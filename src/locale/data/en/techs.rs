@@ -0,0 +1,5 @@
+&[
+    (DSPTech::DysonSphereProgram, "Dyson Sphere Program"),
+    (DSPTech::Electromagnetism, "Electromagnetism"),
+    (DSPTech::ElectromagneticMatrix, "Electromagnetic Matrix"),
+]
@@ -1,7 +1,7 @@
-use std::{collections::HashMap, sync::OnceLock};
+use std::{cell::Cell, collections::HashMap};
 
 use crate::data::{
-    enums::{BPModel, DSPIcon, DSPItem, DSPRecipe},
+    enums::{BPModel, DSPItem, DSPRecipe, DSPSignal, DSPTech},
     traits::TryFromUserString,
 };
 use lazy_static::lazy_static;
@@ -10,8 +10,10 @@ use strum::{EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
 #[allow(non_camel_case_types)]
 #[derive(PartialEq, Eq, Hash, EnumString, EnumIter, IntoStaticStr, Clone, Copy)]
 pub(crate) enum Locale {
+    //Locale enum start
     en,
     cn,
+    //Locale enum end
 }
 
 impl TryFromUserString for Locale {
@@ -26,7 +28,23 @@ impl TryFromUserString for Locale {
     }
 }
 
-pub(crate) static GLOBAL_SERIALIZATION_LOCALE: OnceLock<Locale> = OnceLock::new();
+thread_local! {
+    /// Locale names are serialized in on this thread. Without one, IDs are serialized as
+    /// numbers.
+    static SERIALIZATION_LOCALE: Cell<Option<Locale>> = const { Cell::new(None) };
+}
+
+/// Runs `f` with names serialized in `locale` on this thread.
+pub(crate) fn with_serialization_locale<R>(locale: Locale, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Locale>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SERIALIZATION_LOCALE.set(self.0);
+        }
+    }
+    let _restore = Restore(SERIALIZATION_LOCALE.replace(Some(locale)));
+    f()
+}
 
 pub(crate) trait LocalizedEnum: std::hash::Hash + Eq + PartialEq + Sized {
     fn get_locale_table(&self) -> &'static HashMap<Locale, HashMap<Self, &'static str>>;
@@ -38,24 +56,62 @@ pub(crate) trait LocalizedEnumImpl {
 
 impl<T: LocalizedEnum + 'static> LocalizedEnumImpl for T {
     fn localize(&self) -> Option<&'static str> {
-        let global_locale = GLOBAL_SERIALIZATION_LOCALE.get()?;
-        Some(*self.get_locale_table().get(global_locale)?.get(self)?)
+        name_in(self, SERIALIZATION_LOCALE.get()?)
     }
 }
 
+/// Name of a value in a locale, or its English name if the locale does not name it.
+pub(crate) fn name_in<T: LocalizedEnum + 'static>(
+    value: &T,
+    locale: Locale,
+) -> Option<&'static str> {
+    let table = value.get_locale_table();
+    table
+        .get(&locale)
+        .and_then(|t| t.get(value))
+        .or_else(|| table.get(&Locale::en)?.get(value))
+        .copied()
+}
+
 struct LList<T: 'static>(Locale, &'static [(T, &'static str)]);
 
+// Locales are generated from the directories in data/ by update_data.py. Nothing is generated at
+// build time: a new directory only shows up here once the script is rerun and its output committed.
 static DSP_ITEM_LLIST: &[LList<DSPItem>] = &[
+    //DSP_ITEM_LLIST start
     LList(Locale::en, include!("data/en/items.rs")),
     LList(Locale::cn, include!("data/cn/items.rs")),
+    //DSP_ITEM_LLIST end
 ];
 
 static DSP_RECIPE_LLIST: &[LList<DSPRecipe>] = &[
+    //DSP_RECIPE_LLIST start
     LList(Locale::en, include!("data/en/recipes.rs")),
     LList(Locale::cn, include!("data/cn/recipes.rs")),
+    //DSP_RECIPE_LLIST end
+];
+
+// DSP has no names for models, so they are named after the building that uses them.
+static DSP_MODEL_LLIST: &[LList<BPModel>] = &[
+    //DSP_MODEL_LLIST start
+    LList(Locale::en, include!("data/en/models.rs")),
+    LList(Locale::cn, include!("data/cn/models.rs")),
+    //DSP_MODEL_LLIST end
+];
+
+static DSP_SIGNAL_LLIST: &[LList<DSPSignal>] = &[
+    //DSP_SIGNAL_LLIST start
+    LList(Locale::en, include!("data/en/signals.rs")),
+    LList(Locale::cn, include!("data/cn/signals.rs")),
+    //DSP_SIGNAL_LLIST end
+];
+
+static DSP_TECH_LLIST: &[LList<DSPTech>] = &[
+    //DSP_TECH_LLIST start
+    LList(Locale::en, include!("data/en/techs.rs")),
+    LList(Locale::cn, include!("data/cn/techs.rs")),
+    //DSP_TECH_LLIST end
 ];
-// FIXME how to extract model names from DSP? Console has no commands for that.
-static DSP_MODEL_LLIST: &[LList<BPModel>] = &[];
 
 macro_rules! localized_enum_impl {
     ($enum: ty, $table: ident, $source: ident) => {
@@ -84,33 +140,25 @@ macro_rules! localized_enum_impl {
 localized_enum_impl!(DSPItem, DSP_ENUM_LOCALE, DSP_ITEM_LLIST);
 localized_enum_impl!(DSPRecipe, DSP_RECIPE_LOCALE, DSP_RECIPE_LLIST);
 localized_enum_impl!(BPModel, BP_MODEL_LOCALE, DSP_MODEL_LLIST);
+localized_enum_impl!(DSPSignal, DSP_SIGNAL_LOCALE, DSP_SIGNAL_LLIST);
+localized_enum_impl!(DSPTech, DSP_TECH_LOCALE, DSP_TECH_LLIST);
 
-/// Returns all DSP items as (numeric_id, cn_name) pairs, sorted by id.
-pub fn all_items_cn() -> Vec<(u16, &'static str)> {
-    let mut list: Vec<(u16, &'static str)> = DSP_ITEM_LLIST
-        .iter()
-        .find(|l| l.0 == Locale::cn)
-        .map(|l| {
-            l.1.iter()
-                .map(|(item, name)| {
-                    let id: u16 = (*item).into();
-                    (id, *name)
-                })
-                .collect()
-        })
-        .unwrap_or_default();
-    list.sort_by_key(|(id, _)| *id);
-    list
+/// Names of a value in every locale that names it.
+pub(crate) fn all_names<T: LocalizedEnum + 'static>(
+    value: &T,
+) -> impl Iterator<Item = &'static str> + '_ {
+    value
+        .get_locale_table()
+        .values()
+        .filter_map(move |t| t.get(value).copied())
 }
 
-impl LocalizedEnumImpl for DSPIcon {
-    fn localize(&self) -> Option<&'static str> {
-        match self {
-            DSPIcon::Signal(_) => None,
-            DSPIcon::Item(i) => i.localize(),
-            DSPIcon::Recipe(i) => i.localize(),
-            DSPIcon::Tech(_) => None,
-            DSPIcon::Unknown(_) => None,
-        }
-    }
+/// Returns all DSP items as (numeric_id, name) pairs in a locale, sorted by id.
+#[cfg(feature = "wasm")]
+pub(crate) fn all_items(locale: Locale) -> Vec<(u16, &'static str)> {
+    let mut list: Vec<(u16, &'static str)> = DSPItem::iter()
+        .filter_map(|item| Some((item.into(), name_in(&item, locale)?)))
+        .collect();
+    list.sort_by_key(|(id, _)| *id);
+    list
 }
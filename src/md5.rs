@@ -1,3 +1,16 @@
+//! MD5 and the modified MD5 variants used by Dyson Sphere Program.
+//!
+//! DSP signs blueprints with `Algo::MD5F`, an MD5 with a few altered round constants and a
+//! different initial state. The hash is computed over the blueprint string up to, but not
+//! including, the final `"`, and appended after it as 32 uppercase hex digits.
+//!
+//! ```
+//! use dspbp::md5::{hex, Algo, MD5};
+//!
+//! let hash = MD5::new(Algo::MD5F).process(b"abcd");
+//! assert_eq!(hex(&hash), "FA27C78B6EC31559F0E760CE3F2B03F6");
+//! ```
+
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 
@@ -57,20 +70,42 @@ const INIT_MD5F: [u32; 4] = [
     u32::from_le_bytes([0x46, 0x57, 0x32, 0x10]),
 ];
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+/// Hash algorithm variant.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Algo {
+    /// Standard MD5.
     MD5,
+    /// DSP's modified MD5, used to sign blueprints.
     MD5F,
+    /// Another modified MD5 found in DSP, with a few more altered constants.
     MD5FC,
 }
 
+/// Hasher state. Each instance hashes one message with `process`.
 pub struct MD5 {
     s: [u32; 4],
     algo: Algo,
 }
 
+/// A 16-byte hash.
 pub type MD5Hash = [u8; 16];
 
+/// Formats a hash as uppercase hex, the way DSP writes it.
+pub fn hex(hash: &MD5Hash) -> String {
+    hash.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Parses a hash from 32 hex digits, in either case.
+pub fn parse_hex(s: &str) -> Option<MD5Hash> {
+    if s.len() != 32 || !s.is_ascii() {
+        return None;
+    }
+    (0..16)
+        .map(|x| u8::from_str_radix(&s[2 * x..2 * x + 2], 16).ok())
+        .collect::<Option<Vec<_>>>()
+        .map(|v| v.try_into().unwrap())
+}
+
 impl MD5 {
     pub fn new(algo: Algo) -> Self {
         let s = match algo {
@@ -135,6 +170,7 @@ impl MD5 {
         self.s[3] = self.s[3].wrapping_add(d);
     }
 
+    /// Hashes a complete message.
     pub fn process(&mut self, data: &[u8]) -> MD5Hash {
         let chunks = data.chunks_exact(64);
         let mut last: Vec<u8> = chunks.remainder().into();
@@ -163,7 +199,7 @@ impl MD5 {
 mod test {
     use crate::md5::MD5Hash;
 
-    use super::{Algo, MD5};
+    use super::{hex, parse_hex, Algo, MD5};
 
     #[test]
     fn test_md5_empty() {
@@ -218,4 +254,14 @@ mod test {
         ];
         assert!(hash == expected);
     }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = MD5::new(Algo::MD5F).process(b"a");
+        let s = hex(&hash);
+        assert_eq!(s, "F10BDDAECB62E5A92433757867EE06DB");
+        assert_eq!(parse_hex(&s.to_lowercase()), Some(hash));
+        assert_eq!(parse_hex("F10B"), None);
+        assert_eq!(parse_hex("zz0BDDAECB62E5A92433757867EE06DB"), None);
+    }
 }
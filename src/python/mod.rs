@@ -1,9 +1,23 @@
-use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use pyo3::{
+    create_exception,
+    exceptions::PyValueError,
+    prelude::*,
+    types::{IntoPyDict, PyBytes, PyDict},
+    PyClass,
+};
 
 use crate::{
     blueprint::Blueprint,
-    data::enums::{DSPItem, DSPRecipe},
-    edit::EditBlueprint,
+    data::{
+        enums::{DSPIcon, DSPItem, DSPRecipe},
+        traits::{DSPEnum, TryFromUserString},
+    },
+    edit::{
+        select::Selector,
+        upgrade::{Direction, TierChange},
+        EditBlueprint,
+    },
+    error::Error,
 };
 use std::collections::HashMap;
 
@@ -28,32 +42,209 @@ impl PyBlueprint {
         self.0.get_description().map_err(ve)
     }
 
+    #[setter]
+    pub fn set_description(&mut self, text: &str) -> PyResult<()> {
+        self.0.set_description(text);
+        Ok(())
+    }
+
+    /// The save time as "YYYY-MM-DD HH:MM:SS".
+    #[getter]
+    pub fn get_timestamp(&self) -> String {
+        self.0.get_timestamp()
+    }
+
+    /// Accepts "now" (local time), a date like "2024-01-31 12:30" or a raw tick count.
+    #[setter]
+    pub fn set_timestamp(&mut self, date: &str) -> PyResult<()> {
+        self.0.set_timestamp(date).map_err(ve)
+    }
+
+    /// Fields of the metadata block at the end of the description, in order.
+    #[getter]
+    pub fn get_metadata<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let m = self.0.metadata().map_err(ve)?;
+        Ok(m.fields().iter().cloned().into_py_dict(py))
+    }
+
+    /// Sets a metadata field. `None` or an empty value removes it.
+    #[pyo3(signature = (key, value=None))]
+    pub fn set_metadata(&mut self, key: &str, value: Option<&str>) -> PyResult<()> {
+        self.0.set_metadata(key, value).map_err(ve)
+    }
+
+    #[getter]
+    pub fn get_layout(&self) -> u32 {
+        self.0.get_layout()
+    }
+
+    #[setter]
+    pub fn set_layout(&mut self, layout: u32) -> PyResult<()> {
+        self.0.set_layout(layout);
+        Ok(())
+    }
+
     pub fn info(&mut self) -> PyResult<String> {
         self.0.info().map_err(ve)
     }
 
-    pub fn replace_item(&mut self, map: HashMap<DSPItem, DSPItem>) -> PyResult<()> {
-        self.0.replace_item(map);
+    /// The 5 icons as names like "IronIngot", "recipe:Gear" or "signal:405", or None.
+    #[getter]
+    pub fn get_icons(&self) -> Vec<Option<String>> {
+        self.0
+            .icons()
+            .iter()
+            .map(|i| i.map(|i| i.to_string()))
+            .collect()
+    }
+
+    #[pyo3(signature = (slot, icon=None))]
+    pub fn set_icon(&mut self, slot: usize, icon: Option<&PyAny>) -> PyResult<()> {
+        let icon = icon.map(icon_value).transpose()?;
+        self.0.set_icon(slot, icon).map_err(ve)
+    }
+
+    /// Returns the chosen icon, or None if no building has a recipe.
+    pub fn auto_icon(&mut self) -> Option<String> {
+        self.0.auto_icon().map(|i| i.to_string())
+    }
+
+    #[pyo3(signature = (map, r#where=None))]
+    pub fn replace_item(&mut self, map: &PyDict, r#where: Option<&str>) -> PyResult<()> {
+        let s = selector(r#where)?;
+        self.0
+            .replace_item_where(enum_map::<DSPItem>(map)?, s.as_ref());
         Ok(())
     }
 
-    pub fn replace_recipe(&mut self, map: HashMap<DSPRecipe, DSPRecipe>) -> PyResult<()> {
-        self.0.replace_recipe(map);
+    #[pyo3(signature = (map, r#where=None))]
+    pub fn replace_recipe(&mut self, map: &PyDict, r#where: Option<&str>) -> PyResult<()> {
+        let s = selector(r#where)?;
+        self.0
+            .replace_recipe_where(enum_map::<DSPRecipe>(map)?, s.as_ref());
         Ok(())
     }
 
-    pub fn replace_both(&mut self, r: HashMap<DSPItem, DSPItem>) -> PyResult<()> {
-        self.0.replace_both(r);
+    #[pyo3(signature = (r, r#where=None))]
+    pub fn replace_both(&mut self, r: &PyDict, r#where: Option<&str>) -> PyResult<()> {
+        let s = selector(r#where)?;
+        self.0
+            .replace_both_where(enum_map::<DSPItem>(r)?, s.as_ref());
         Ok(())
     }
 
-    pub fn replace_building(&mut self, map: HashMap<DSPItem, DSPItem>) -> PyResult<()> {
-        self.0.replace_building(map).map_err(ve)
+    #[pyo3(signature = (map, r#where=None))]
+    pub fn replace_building(&mut self, map: &PyDict, r#where: Option<&str>) -> PyResult<()> {
+        let s = selector(r#where)?;
+        self.0
+            .replace_building_where(enum_map::<DSPItem>(map)?, s.as_ref())
+            .map_err(ve)
+    }
+
+    /// Returns (changed, unmapped), both lists of (from, to, count).
+    #[pyo3(signature = (tier=None, families=None, r#where=None))]
+    pub fn upgrade_all(
+        &mut self,
+        tier: Option<usize>,
+        families: Option<Vec<String>>,
+        r#where: Option<&str>,
+    ) -> PyResult<(TierList, TierList)> {
+        self.change_tiers(Direction::Up, tier, families, r#where)
+    }
+
+    /// Returns (changed, unmapped), both lists of (from, to, count).
+    #[pyo3(signature = (tier=None, families=None, r#where=None))]
+    pub fn downgrade_all(
+        &mut self,
+        tier: Option<usize>,
+        families: Option<Vec<String>>,
+        r#where: Option<&str>,
+    ) -> PyResult<(TierList, TierList)> {
+        self.change_tiers(Direction::Down, tier, families, r#where)
+    }
+
+    /// Mode is "extra_products" or "speedup". Returns the number of buildings changed.
+    #[pyo3(signature = (mode, r#where=None))]
+    pub fn set_proliferator_mode(&mut self, mode: &str, r#where: Option<&str>) -> PyResult<usize> {
+        let mode = mode.parse().map_err(ve)?;
+        let s = selector(r#where)?;
+        Ok(self.0.set_proliferator_mode_where(mode, s.as_ref()))
+    }
+}
+
+type TierList = Vec<(DSPItem, DSPItem, usize)>;
+
+impl PyBlueprint {
+    fn change_tiers(
+        &mut self,
+        direction: Direction,
+        tier: Option<usize>,
+        families: Option<Vec<String>>,
+        r#where: Option<&str>,
+    ) -> PyResult<(TierList, TierList)> {
+        let change = TierChange {
+            direction,
+            tier,
+            families: families.unwrap_or_default(),
+        };
+        let s = selector(r#where)?;
+        let report = self.0.change_tiers(&change, s.as_ref()).map_err(ve)?;
+        Ok((report.changed, report.unmapped))
     }
 }
 
+create_exception!(dspbp, BlueprintError, PyValueError);
+create_exception!(dspbp, HashMismatchError, BlueprintError);
+create_exception!(dspbp, BlueprintFormatError, BlueprintError);
+create_exception!(dspbp, BlueprintDecodeError, BlueprintError);
+
 fn ve(e: anyhow::Error) -> PyErr {
-    PyValueError::new_err(e.to_string())
+    let msg = format!("{:#}", e);
+    match e.downcast_ref::<Error>() {
+        Some(Error::HashMismatch { .. }) => HashMismatchError::new_err(msg),
+        Some(Error::Decode {
+            offset, building, ..
+        }) => {
+            let err = BlueprintDecodeError::new_err(msg);
+            let attrs = Python::with_gil(|py| {
+                let value = err.value(py);
+                value.setattr("offset", *offset)?;
+                value.setattr("building", building.map(|b| b.position))
+            });
+            attrs.map_or_else(|e| e, |_| err)
+        }
+        Some(Error::E(_)) | None => PyValueError::new_err(msg),
+        Some(_) => BlueprintFormatError::new_err(msg),
+    }
+}
+
+/// An enum member, or a numeric ID or name in any form `dspbp` accepts on the command line.
+fn enum_value<T: DSPEnum + PyClass + Clone>(v: &PyAny) -> PyResult<T> {
+    if let Ok(e) = v.extract::<T>() {
+        return Ok(e);
+    }
+    T::try_from_user_string(v.str()?.to_str()?).map_err(ve)
+}
+
+/// An item or recipe enum member, or an icon in any form the command line accepts.
+fn icon_value(v: &PyAny) -> PyResult<DSPIcon> {
+    if let Ok(i) = v.extract::<DSPItem>() {
+        return Ok(DSPIcon::Item(i));
+    }
+    if let Ok(r) = v.extract::<DSPRecipe>() {
+        return Ok(DSPIcon::Recipe(r));
+    }
+    DSPIcon::try_from_user_string(v.str()?.to_str()?).map_err(ve)
+}
+
+fn enum_map<T: DSPEnum + PyClass + Clone>(map: &PyDict) -> PyResult<HashMap<T, T>> {
+    map.iter()
+        .map(|(k, v)| Ok((enum_value(k)?, enum_value(v)?)))
+        .collect()
+}
+
+fn selector(s: Option<&str>) -> PyResult<Option<Selector>> {
+    s.map(|s| s.parse::<Selector>()).transpose().map_err(ve)
 }
 
 #[pyfunction]
@@ -64,6 +255,25 @@ fn load(buf: &PyAny) -> PyResult<PyBlueprint> {
     Ok(PyBlueprint(EditBlueprint::new(bp)))
 }
 
+#[pyfunction]
+fn sign<'a>(py: Python<'a>, buf: &PyAny) -> PyResult<&'a PyBytes> {
+    let bytes = buf.downcast::<PyBytes>()?;
+    let data = std::str::from_utf8(bytes.as_bytes())?;
+    let signed = Blueprint::sign(data).map_err(ve)?;
+    Ok(PyBytes::new(py, signed.as_bytes()))
+}
+
+#[pyfunction]
+fn check_hash(buf: &PyAny) -> PyResult<bool> {
+    let bytes = buf.downcast::<PyBytes>()?;
+    let data = std::str::from_utf8(bytes.as_bytes())?;
+    match Blueprint::check_hash(data) {
+        Ok(()) => Ok(true),
+        Err(Error::HashMismatch { .. }) => Ok(false),
+        Err(e) => Err(ve(e.into())),
+    }
+}
+
 #[pyfunction]
 fn save<'a>(py: Python<'a>, bp: &PyBlueprint, compression_level: Option<u32>) -> PyResult<&'a PyBytes> {
     let cl = compression_level.unwrap_or(6);
@@ -72,11 +282,23 @@ fn save<'a>(py: Python<'a>, bp: &PyBlueprint, compression_level: Option<u32>) ->
 }
 
 #[pymodule]
-fn dspbp(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+fn dspbp(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(load, m)?)?;
     m.add_function(wrap_pyfunction!(save, m)?)?;
+    m.add_function(wrap_pyfunction!(sign, m)?)?;
+    m.add_function(wrap_pyfunction!(check_hash, m)?)?;
     m.add_class::<PyBlueprint>()?;
     m.add_class::<DSPItem>()?;
     m.add_class::<DSPRecipe>()?;
+    m.add("BlueprintError", py.get_type::<BlueprintError>())?;
+    m.add("HashMismatchError", py.get_type::<HashMismatchError>())?;
+    m.add(
+        "BlueprintFormatError",
+        py.get_type::<BlueprintFormatError>(),
+    )?;
+    m.add(
+        "BlueprintDecodeError",
+        py.get_type::<BlueprintDecodeError>(),
+    )?;
     Ok(())
 }
@@ -0,0 +1,475 @@
+//! Edit scripts: ordered lists of edit operations in TOML, run by `dspbp apply`.
+//!
+//! ```toml
+//! inputs = ["library/smelting"]
+//! output = "out/{stem}.txt"
+//!
+//! [[op]]
+//! type = "replace_building"
+//! map = { ArcSmelter = "PlaneSmelter" }
+//! where = "area=0"
+//!
+//! [[op]]
+//! type = "set_icon_text"
+//! text = "Smelting v2"
+//! ```
+//!
+//! Inputs are files or directories searched for *.txt blueprints, relative to the script.
+//! Directory searches skip files that are the output of another file found, so running a
+//! script again does not edit its earlier output.
+//! The output pattern can use `{dir}` (directory of the input), `{stem}` (file name without
+//! extension) and `{name}` (file name).
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    blueprint::Blueprint,
+    data::{
+        assembler::ProliferatorMode,
+        enums::{DSPItem, DSPRecipe},
+        traits::{DSPEnum, TryFromUserString},
+    },
+    edit::{
+        select::Selector,
+        station::StationSettings,
+        tile::TileOptions,
+        upgrade::{Direction, TierChange},
+        EditBlueprint,
+    },
+    error::some_error,
+    verify::collect_blueprints,
+};
+
+fn default_compression_level() -> u32 {
+    6
+}
+
+fn default_output() -> String {
+    "{dir}/{stem}.edited.txt".to_owned()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Script {
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    #[serde(default = "default_output")]
+    pub output: String,
+    #[serde(default = "default_compression_level")]
+    pub compression_level: u32,
+    #[serde(rename = "op", default)]
+    pub ops: Vec<Op>,
+}
+
+/// One edit operation. `where` limits an operation to buildings matching a selector.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Op {
+    ReplaceItem {
+        map: BTreeMap<String, String>,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    ReplaceRecipe {
+        map: BTreeMap<String, String>,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    ReplaceBoth {
+        map: BTreeMap<String, String>,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    ReplaceBuilding {
+        map: BTreeMap<String, String>,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    SetIconText {
+        text: String,
+    },
+    SetDescription {
+        text: String,
+    },
+    /// `date` is "now", a date like "2024-01-31 12:30" or a raw tick count.
+    SetTimestamp {
+        date: String,
+    },
+    SetLayout {
+        layout: u32,
+    },
+    /// Up to 5 icons in DSP encoding.
+    SetIcons {
+        icons: Vec<u32>,
+    },
+    SetStation {
+        station: StationSettings,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    /// `mode` is "extra_products" or "speedup".
+    SetProliferatorMode {
+        mode: String,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    /// Moves buildings up their upgrade families, to `tier` or the highest tier.
+    Upgrade {
+        tier: Option<usize>,
+        #[serde(default)]
+        families: Vec<String>,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    /// Moves buildings down their upgrade families, to `tier` or the lowest tier.
+    Downgrade {
+        tier: Option<usize>,
+        #[serde(default)]
+        families: Vec<String>,
+        #[serde(rename = "where")]
+        selector: Option<String>,
+    },
+    Tile {
+        columns: u32,
+        rows: u32,
+        dx: Option<f32>,
+        dy: Option<f32>,
+        #[serde(default)]
+        chain_belts: bool,
+    },
+    MoveToArea {
+        #[serde(rename = "where")]
+        selector: String,
+        to: i8,
+    },
+    FlattenAreas,
+}
+
+fn enum_map<T: DSPEnum + 'static>(m: &BTreeMap<String, String>) -> anyhow::Result<HashMap<T, T>> {
+    m.iter()
+        .map(|(k, v)| Ok((T::try_from_user_string(k)?, T::try_from_user_string(v)?)))
+        .collect()
+}
+
+fn selector(s: &Option<String>) -> anyhow::Result<Option<Selector>> {
+    s.as_ref().map(|s| s.parse()).transpose()
+}
+
+impl Op {
+    fn tier_change(&self) -> Option<TierChange> {
+        let (direction, tier, families) = match self {
+            Self::Upgrade { tier, families, .. } => (Direction::Up, tier, families),
+            Self::Downgrade { tier, families, .. } => (Direction::Down, tier, families),
+            _ => return None,
+        };
+        Some(TierChange {
+            direction,
+            tier: *tier,
+            families: families.clone(),
+        })
+    }
+
+    /// Checks item names, recipe names and selectors without touching a blueprint.
+    pub fn check(&self) -> anyhow::Result<()> {
+        match self {
+            Self::ReplaceItem { map, selector: s }
+            | Self::ReplaceBoth { map, selector: s }
+            | Self::ReplaceBuilding { map, selector: s } => {
+                enum_map::<DSPItem>(map)?;
+                selector(s)?;
+            }
+            Self::ReplaceRecipe { map, selector: s } => {
+                enum_map::<DSPRecipe>(map)?;
+                selector(s)?;
+            }
+            Self::SetStation {
+                station,
+                selector: s,
+            } => {
+                station.check()?;
+                selector(s)?;
+            }
+            Self::SetProliferatorMode { mode, selector: s } => {
+                mode.parse::<ProliferatorMode>()?;
+                selector(s)?;
+            }
+            Self::MoveToArea { selector: s, .. } => {
+                s.parse::<Selector>()?;
+            }
+            Self::Upgrade { selector: s, .. } | Self::Downgrade { selector: s, .. } => {
+                self.tier_change().unwrap().check()?;
+                selector(s)?;
+            }
+            Self::SetIcons { icons } if icons.len() > 5 => {
+                return Err(some_error(format!(
+                    "Expected at most 5 icons, got {}",
+                    icons.len()
+                )))
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, bp: &mut EditBlueprint) -> anyhow::Result<()> {
+        match self {
+            Self::ReplaceItem { map, selector: s } => {
+                bp.replace_item_where(enum_map(map)?, selector(s)?.as_ref())
+            }
+            Self::ReplaceRecipe { map, selector: s } => {
+                bp.replace_recipe_where(enum_map(map)?, selector(s)?.as_ref())
+            }
+            Self::ReplaceBoth { map, selector: s } => {
+                bp.replace_both_where(enum_map(map)?, selector(s)?.as_ref())
+            }
+            Self::ReplaceBuilding { map, selector: s } => {
+                bp.replace_building_where(enum_map(map)?, selector(s)?.as_ref())?
+            }
+            Self::SetIconText { text } => bp.set_icon_text(text),
+            Self::SetDescription { text } => bp.set_description(text),
+            Self::SetTimestamp { date } => bp.set_timestamp(date)?,
+            Self::SetLayout { layout } => bp.set_layout(*layout),
+            Self::SetIcons { icons } => bp.set_icons(icons)?,
+            Self::SetStation {
+                station,
+                selector: s,
+            } => bp.set_station_where(station, selector(s)?.as_ref())?,
+            Self::SetProliferatorMode { mode, selector: s } => {
+                bp.set_proliferator_mode_where(mode.parse()?, selector(s)?.as_ref());
+            }
+            Self::Upgrade { selector: s, .. } | Self::Downgrade { selector: s, .. } => {
+                bp.change_tiers(&self.tier_change().unwrap(), selector(s)?.as_ref())?;
+            }
+            Self::Tile {
+                columns,
+                rows,
+                dx,
+                dy,
+                chain_belts,
+            } => {
+                bp.tile(&TileOptions {
+                    columns: *columns,
+                    rows: *rows,
+                    spacing_x: *dx,
+                    spacing_y: *dy,
+                    chain_belts: *chain_belts,
+                })?;
+            }
+            Self::MoveToArea { selector: s, to } => {
+                let positions = s.parse::<Selector>()?.positions(&bp.0.data);
+                bp.move_to_area(&positions, *to)?
+            }
+            Self::FlattenAreas => {
+                bp.flatten_areas()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of running a script on one input file: the output path, or the error.
+pub type FileResult = (PathBuf, anyhow::Result<PathBuf>);
+
+impl Script {
+    /// Parses a script and checks all of its operations.
+    pub fn from_toml(s: &str) -> anyhow::Result<Self> {
+        let script: Self = toml::from_str(s)?;
+        for (i, op) in script.ops.iter().enumerate() {
+            op.check()
+                .map_err(|e| some_error(format!("Operation {}: {:#}", i + 1, e)))?;
+        }
+        Ok(script)
+    }
+
+    /// Runs all operations, in order, on a blueprint.
+    pub fn apply(&self, bp: &mut EditBlueprint) -> anyhow::Result<()> {
+        for (i, op) in self.ops.iter().enumerate() {
+            op.apply(bp)
+                .map_err(|e| some_error(format!("Operation {}: {:#}", i + 1, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Runs the script on a blueprint string, returning the edited blueprint string.
+    pub fn apply_str(&self, bp_string: &str) -> anyhow::Result<String> {
+        let mut bp = EditBlueprint::new(Blueprint::new(bp_string.trim())?);
+        self.apply(&mut bp)?;
+        bp.0.into_bp_string(self.compression_level)
+    }
+
+    fn output_path(&self, base: &Path, input: &Path) -> PathBuf {
+        let dir = input.parent().unwrap_or(Path::new("."));
+        let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+        let name = input.file_name().unwrap_or_default().to_string_lossy();
+        let out = self
+            .output
+            .replace("{dir}", &dir.to_string_lossy())
+            .replace("{stem}", &stem)
+            .replace("{name}", &name);
+        base.join(out)
+    }
+
+    /// Runs the script on its inputs, or on `inputs` if not empty. Relative paths are
+    /// resolved against `base`, normally the script's directory.
+    pub fn run(&self, base: &Path, inputs: &[String]) -> anyhow::Result<Vec<FileResult>> {
+        let inputs = if inputs.is_empty() {
+            &self.inputs
+        } else {
+            inputs
+        };
+        let mut files = vec![];
+        for i in inputs.iter() {
+            let path = base.join(i);
+            if path.is_dir() {
+                let mut found = vec![];
+                collect_blueprints(&path, &mut found)?;
+                found.sort();
+                // Skip earlier outputs, like "a.edited.txt" next to "a.txt", so a rerun does
+                // not edit them again.
+                let outputs: Vec<PathBuf> =
+                    found.iter().map(|f| self.output_path(base, f)).collect();
+                files.extend(found.into_iter().filter(|f| !outputs.contains(f)));
+            } else {
+                files.push(path);
+            }
+        }
+        if files.is_empty() {
+            return Err(some_error("Script has no input files"));
+        }
+
+        let outputs: Vec<PathBuf> = files.iter().map(|f| self.output_path(base, f)).collect();
+        for (i, o) in outputs.iter().enumerate() {
+            if let Some(j) = outputs[..i].iter().position(|p| p == o) {
+                return Err(some_error(format!(
+                    "{} and {} would both be written to {}",
+                    files[j].display(),
+                    files[i].display(),
+                    o.display()
+                )));
+            }
+            if files.contains(o) {
+                return Err(some_error(format!(
+                    "Output {} would overwrite an input",
+                    o.display()
+                )));
+            }
+        }
+
+        Ok(files
+            .into_iter()
+            .zip(outputs)
+            .map(|(input, output)| {
+                let res = std::fs::read_to_string(&input)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|s| self.apply_str(&s))
+                    .and_then(|s| {
+                        if let Some(dir) = output.parent() {
+                            std::fs::create_dir_all(dir)?;
+                        }
+                        std::fs::write(&output, s)?;
+                        Ok(output)
+                    });
+                (input, res)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Script;
+    use crate::{
+        blueprint::Blueprint,
+        data::{
+            assembler::ProliferatorMode, building::BuildingParam, enums::DSPItem, visit::Visitor,
+        },
+        edit::stats::GetStats,
+        testutil::get_file,
+    };
+
+    const SCRIPT: &str = r#"
+        [[op]]
+        type = "replace_building"
+        map = { AssemblingMachineMkIII = "AssemblingMachineMkI" }
+        where = "bbox=0,0,20,24"
+
+        [[op]]
+        type = "replace_building"
+        map = { AssemblingMachineMkI = "AssemblingMachineMkII" }
+
+        [[op]]
+        type = "set_icon_text"
+        text = "Edited"
+
+        [[op]]
+        type = "set_station"
+        station = { drone_range_degrees = 40 }
+
+        [[op]]
+        type = "set_proliferator_mode"
+        mode = "speedup"
+        where = "item=AssemblingMachineMkII"
+    "#;
+
+    #[test]
+    fn ops_run_in_order() {
+        let script = Script::from_toml(SCRIPT).unwrap();
+        let f = get_file("旧版本5飞机.txt");
+        let out = script.apply_str(std::str::from_utf8(&f).unwrap()).unwrap();
+        let mut bp = Blueprint::new(&out).unwrap();
+        assert_eq!(bp.get_icon_text().unwrap(), "Edited");
+        let mut stats = GetStats::new();
+        stats.visit_blueprint(&mut bp);
+        let s = format!("{}", stats.0);
+        assert!(!s.contains("AssemblingMachineMkI:"));
+        assert!(s.contains(DSPItem::AssemblingMachineMkII.as_ref()));
+        let modes = bp
+            .data
+            .buildings
+            .iter()
+            .filter(|b| b.header.item_id == DSPItem::AssemblingMachineMkII.into())
+            .map(|b| match &b.param {
+                BuildingParam::Assembler(a) => a.mode(),
+                _ => panic!("assembler params not decoded"),
+            });
+        assert!(modes.into_iter().all(|m| m == ProliferatorMode::Speedup));
+    }
+
+    #[test]
+    fn rerun_skips_outputs() {
+        let base = std::env::temp_dir().join(format!("dspbp-script-{}", std::process::id()));
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::write(base.join("a.txt"), get_file("旧版本5飞机.txt")).unwrap();
+        let script =
+            Script::from_toml("inputs = [\".\"]\n[[op]]\ntype = \"set_icon_text\"\ntext = \"x\"")
+                .unwrap();
+        for _ in 0..2 {
+            let results = script.run(&base, &[]).unwrap();
+            assert_eq!(results.len(), 1);
+            assert!(results[0].0.ends_with("a.txt"));
+            assert!(results[0].1.as_ref().unwrap().ends_with("a.edited.txt"));
+        }
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn bad_scripts_are_rejected() {
+        for bad in [
+            "[[op]]\ntype = \"replace_item\"\nmap = { NoSuchItem = \"IronOre\" }",
+            "[[op]]\ntype = \"replace_item\"\nmap = {}\nwhere = \"colour=red\"",
+            "[[op]]\ntype = \"explode\"",
+            "[[op]]\ntype = \"set_icon_text\"\ntext = \"a\"\nfont = \"b\"",
+            "[[op]]\ntype = \"set_station\"\nstation = { drone_range = 1 }",
+            "[[op]]\ntype = \"set_station\"\nstation = { vessel_range_ly = 200000 }",
+            "[[op]]\ntype = \"set_proliferator_mode\"\nmode = \"faster\"",
+            "[[op]]\ntype = \"upgrade\"\nfamilies = [\"bolt\"]",
+        ] {
+            assert!(Script::from_toml(bad).is_err(), "{}", bad);
+        }
+    }
+}
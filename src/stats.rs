@@ -1,4 +1,7 @@
-use crate::data::enums::{DSPItem, DSPRecipe};
+use crate::data::{
+    assembler::ProliferatorMode,
+    enums::{DSPItem, DSPRecipe},
+};
 use std::{collections::HashMap, fmt::Display, hash::Hash};
 
 #[derive(Default)]
@@ -6,6 +9,10 @@ pub struct Stats {
     pub buildings: HashMap<DSPItem, usize>,
     pub recipes: HashMap<DSPRecipe, usize>,
     pub station_wares: HashMap<DSPItem, usize>,
+    /// Production buildings set to extra products.
+    pub extra_products: usize,
+    /// Production buildings set to production speedup.
+    pub speedup: usize,
 }
 
 impl Stats {
@@ -46,18 +53,46 @@ impl Stats {
     pub fn add_station_ware(&mut self, i: DSPItem) {
         Self::incmap(&mut self.station_wares, i)
     }
+
+    pub fn add_proliferator_mode(&mut self, m: ProliferatorMode) {
+        match m {
+            ProliferatorMode::ExtraProducts => self.extra_products += 1,
+            ProliferatorMode::Speedup => self.speedup += 1,
+        }
+    }
+
+    pub fn spray_coaters(&self) -> usize {
+        *self.buildings.get(&DSPItem::SprayCoater).unwrap_or(&0)
+    }
 }
 
 impl Display for Stats {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Buildings:")?;
         Self::printmap(f, &self.buildings)?;
-        writeln!(f, "")?;
+        writeln!(f)?;
         writeln!(f, "Recipes:")?;
         Self::printmap(f, &self.recipes)?;
-        writeln!(f, "")?;
+        writeln!(f)?;
         writeln!(f, "Logistic station wares:")?;
         Self::printmap(f, &self.station_wares)?;
+        if self.extra_products + self.speedup == 0 {
+            return Ok(());
+        }
+        writeln!(f)?;
+        writeln!(f, "Proliferator:")?;
+        writeln!(f, "Extra products: {}", self.extra_products)?;
+        writeln!(f, "Production speedup: {}", self.speedup)?;
+        writeln!(f, "Spray coaters: {}", self.spray_coaters())?;
+        if self.spray_coaters() == 0 {
+            writeln!(
+                f,
+                "Note: no spray coaters, inputs are only proliferated if they arrive sprayed."
+            )?;
+        }
+        if self.extra_products != 0 && self.speedup != 0 {
+            writeln!(f, "Note: mixed proliferator modes.")?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,183 @@
+//! Blueprint timestamps.
+//!
+//! DSP stores the time a blueprint was saved as .NET ticks: 100 ns intervals since
+//! 0001-01-01 00:00:00, in the player's local time. Dates are converted as written, without
+//! a time zone. "now" is the current local time.
+//!
+//! ```
+//! use dspbp::timestamp::{format, parse};
+//!
+//! let ticks = parse("2023-12-22 01:12:45").unwrap();
+//! assert_eq!(format(ticks), "2023-12-22 01:12:45");
+//! ```
+
+use crate::error::some_error;
+
+const TICKS_PER_SECOND: u64 = 10_000_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+/// Days from 0001-01-01 to 1970-01-01.
+const UNIX_EPOCH_DAYS: i64 = 719_162;
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = m as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of `days_from_civil`.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (
+        if m <= 2 {
+            yoe + era * 400 + 1
+        } else {
+            yoe + era * 400
+        },
+        m,
+        d,
+    )
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        2 if y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Formats ticks as "YYYY-MM-DD HH:MM:SS".
+pub fn format(ticks: u64) -> String {
+    let seconds = ticks / TICKS_PER_SECOND;
+    let days = (seconds / SECONDS_PER_DAY) as i64 - UNIX_EPOCH_DAYS;
+    let (y, m, d) = civil_from_days(days);
+    let s = seconds % SECONDS_PER_DAY;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        y,
+        m,
+        d,
+        s / 3600,
+        s / 60 % 60,
+        s % 60
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> anyhow::Result<u64> {
+    let now = time::OffsetDateTime::now_local().map_err(|_| {
+        some_error("The local time zone is not known, give a date instead of \"now\"")
+    })?;
+    let seconds = UNIX_EPOCH_DAYS * SECONDS_PER_DAY as i64
+        + now.unix_timestamp()
+        + now.offset().whole_seconds() as i64;
+    Ok(seconds as u64 * TICKS_PER_SECOND + now.nanosecond() as u64 / 100)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> anyhow::Result<u64> {
+    Err(some_error(
+        "\"now\" is not available here, give a date instead",
+    ))
+}
+
+/// Parses "now" (in local time), a date like "2024-01-31", a date and time like "2024-01-31 12:30"
+/// or "2024-01-31T12:30:15", or a raw tick count.
+pub fn parse(s: &str) -> anyhow::Result<u64> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("now") {
+        return now();
+    }
+    if let Ok(ticks) = s.parse::<u64>() {
+        return Ok(ticks);
+    }
+    let bad = || {
+        some_error(format!(
+            "'{}' is not a date. Use \"now\", YYYY-MM-DD, \"YYYY-MM-DD HH:MM[:SS]\" or a tick count",
+            s
+        ))
+    };
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, time.trim()),
+        None => (s, ""),
+    };
+    let date: Vec<&str> = date.split('-').collect();
+    let [y, m, d] = date[..] else {
+        return Err(bad());
+    };
+    let (y, m, d): (i64, u32, u32) = (
+        y.parse().map_err(|_| bad())?,
+        m.parse().map_err(|_| bad())?,
+        d.parse().map_err(|_| bad())?,
+    );
+    if !(1..=9999).contains(&y) || !(1..=12).contains(&m) || d < 1 || d > days_in_month(y, m) {
+        return Err(bad());
+    }
+    let time: Vec<u64> = if time.is_empty() {
+        vec![]
+    } else {
+        time.split(':')
+            .map(|t| t.parse().map_err(|_| bad()))
+            .collect::<anyhow::Result<_>>()?
+    };
+    let (h, min, sec) = match time[..] {
+        [] => (0, 0, 0),
+        [h, min] => (h, min, 0),
+        [h, min, sec] => (h, min, sec),
+        _ => return Err(bad()),
+    };
+    if h > 23 || min > 59 || sec > 59 {
+        return Err(bad());
+    }
+    let days = (days_from_civil(y, m, d) + UNIX_EPOCH_DAYS) as u64;
+    Ok((days * SECONDS_PER_DAY + h * 3600 + min * 60 + sec) * TICKS_PER_SECOND)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format, parse, SECONDS_PER_DAY, TICKS_PER_SECOND, UNIX_EPOCH_DAYS};
+
+    #[test]
+    fn dates() {
+        // From the example blueprints.
+        assert_eq!(format(638388043650062144), "2023-12-22 01:12:45");
+        assert_eq!(parse("2023-12-22 01:12:45").unwrap(), 638388043650000000);
+        assert_eq!(parse("2023-12-22T01:12:45").unwrap(), 638388043650000000);
+        assert_eq!(format(parse("2024-02-29").unwrap()), "2024-02-29 00:00:00");
+        assert_eq!(
+            format(parse("0001-01-01 00:00").unwrap()),
+            "0001-01-01 00:00:00"
+        );
+        assert_eq!(parse("638388043650062144").unwrap(), 638388043650062144);
+        // "now" is local time, at most 14 hours off UTC.
+        let utc = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + (UNIX_EPOCH_DAYS as u64 * SECONDS_PER_DAY);
+        let local = parse("now").unwrap() / TICKS_PER_SECOND;
+        assert!(local.abs_diff(utc) <= 14 * 3600, "{} vs {}", local, utc);
+        for bad in [
+            "2023-02-29",
+            "2023-13-01",
+            "2023-12-22 24:00",
+            "yesterday",
+            "2023-12",
+        ] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+    }
+}
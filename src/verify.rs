@@ -0,0 +1,193 @@
+use std::fmt::Display;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use binrw::BinWrite;
+
+use crate::blueprint::Blueprint;
+use crate::data::blueprint::BlueprintData;
+use crate::error::BuildingContext;
+use crate::version::with_game_version;
+
+/// Part of the binary payload that a byte offset falls into.
+#[derive(Debug, PartialEq)]
+pub enum Location {
+    /// Blueprint header, areas or building count.
+    Header,
+    Building(BuildingContext),
+    /// Bytes after the last building.
+    Trailing,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Header => write!(f, "blueprint header"),
+            Self::Building(b) => write!(f, "{}", b),
+            Self::Trailing => write!(f, "data after the last building"),
+        }
+    }
+}
+
+/// First difference between the original payload and the re-serialized one.
+#[derive(Debug)]
+pub struct Mismatch {
+    pub offset: usize,
+    pub original: Option<u8>,
+    pub reserialized: Option<u8>,
+    pub location: Location,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let byte = |b: Option<u8>| match b {
+            Some(b) => format!("0x{:02x}", b),
+            None => "end of data".to_owned(),
+        };
+        write!(
+            f,
+            "Mismatch at offset {} (0x{:x}) in {}: original {}, re-serialized {}",
+            self.offset,
+            self.offset,
+            self.location,
+            byte(self.original),
+            byte(self.reserialized)
+        )
+    }
+}
+
+/// Serializes blueprint data, returning the bytes and the start offset of every building.
+fn write_with_offsets(data: &BlueprintData) -> anyhow::Result<(Vec<u8>, Vec<usize>)> {
+    let mut c = Cursor::new(vec![]);
+    data.header.write_le(&mut c)?;
+    data.areas.write_le(&mut c)?;
+    data.building_count.write_le(&mut c)?;
+    let mut offsets = Vec::with_capacity(data.buildings.len());
+    for b in data.buildings.iter() {
+        offsets.push(c.position() as usize);
+        b.write_le(&mut c)?;
+    }
+    Ok((c.into_inner(), offsets))
+}
+
+fn locate(data: &BlueprintData, offsets: &[usize], end: usize, offset: usize) -> Location {
+    if offset >= end {
+        return Location::Trailing;
+    }
+    match offsets.partition_point(|o| *o <= offset) {
+        0 => Location::Header,
+        n => {
+            let b = &data.buildings[n - 1];
+            Location::Building(BuildingContext {
+                position: n - 1,
+                index: Some(b.header.index),
+                item: Some(b.header.item_id),
+            })
+        }
+    }
+}
+
+/// Checks that a blueprint's binary payload survives parsing and re-serialization byte for byte.
+///
+/// Returns `None` if the payloads are identical, or the first mismatch otherwise.
+pub fn verify(bp_string: &str) -> anyhow::Result<Option<Mismatch>> {
+    let (bp, raw) = Blueprint::new_with_raw_bp(bp_string)?;
+    verify_parsed(&bp, &raw)
+}
+
+/// Like `verify`, for an already parsed blueprint and its decompressed payload.
+pub fn verify_parsed(bp: &Blueprint, raw: &[u8]) -> anyhow::Result<Option<Mismatch>> {
+    let (back, offsets) = with_game_version(&bp.game_version, || write_with_offsets(&bp.data))?;
+    let offset = match raw.iter().zip(back.iter()).position(|(a, b)| a != b) {
+        Some(o) => o,
+        None if raw.len() == back.len() => return Ok(None),
+        None => std::cmp::min(raw.len(), back.len()),
+    };
+    Ok(Some(Mismatch {
+        offset,
+        original: raw.get(offset).copied(),
+        reserialized: back.get(offset).copied(),
+        location: locate(&bp.data, &offsets, back.len(), offset),
+    }))
+}
+
+/// Result of verifying one file of a corpus.
+pub type FileResult = (PathBuf, anyhow::Result<Option<Mismatch>>);
+
+/// Runs `verify` over every *.txt blueprint in a directory, recursively.
+pub fn verify_dir(dir: &Path) -> std::io::Result<Vec<FileResult>> {
+    let mut files = vec![];
+    collect_blueprints(dir, &mut files)?;
+    files.sort();
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let res = std::fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|d| Ok(String::from_utf8(d)?))
+                .and_then(|s| verify(s.trim()));
+            (path, res)
+        })
+        .collect())
+}
+
+/// Appends every *.txt file under `dir` to `out`, recursively.
+pub(crate) fn collect_blueprints(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    collect_files(dir, "txt", out)
+}
+
+/// Appends every file with the given extension under `dir` to `out`, recursively.
+pub(crate) fn collect_files(dir: &Path, ext: &str, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, ext, out)?;
+        } else if path.extension().and_then(|e| e.to_str()) == Some(ext) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{verify_dir, verify_parsed, Location};
+    use crate::{blueprint::Blueprint, testutil::get_file};
+
+    /// Corpus harness. Verifies examples/, or the directory in DSPBP_CORPUS if set.
+    #[test]
+    fn corpus_round_trips_byte_exact() {
+        let dir = match std::env::var_os("DSPBP_CORPUS") {
+            Some(d) => PathBuf::from(d),
+            None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("examples"),
+        };
+        let results = verify_dir(&dir).unwrap();
+        assert!(!results.is_empty(), "No blueprints in {}", dir.display());
+        let failures: Vec<String> = results
+            .iter()
+            .filter_map(|(path, res)| match res {
+                Ok(None) => None,
+                Ok(Some(m)) => Some(format!("{}: {}", path.display(), m)),
+                Err(e) => Some(format!("{}: {:#}", path.display(), e)),
+            })
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn mismatch_points_at_building() {
+        let f = get_file("旧版本5飞机.txt");
+        let (mut bp, raw) =
+            Blueprint::new_with_raw_bp(std::str::from_utf8(&f).unwrap().trim()).unwrap();
+        bp.data.buildings[3].header.yaw += 1.0;
+        let m = verify_parsed(&bp, &raw)
+            .unwrap()
+            .expect("expected a mismatch");
+        match m.location {
+            Location::Building(b) => assert_eq!(b.position, 3),
+            l => panic!("unexpected location {:?}", l),
+        }
+    }
+}
@@ -5,13 +5,19 @@ use wasm_bindgen::prelude::*;
 use crate::{
     blueprint::Blueprint,
     data::{
-        enums::{DSPItem, DSPRecipe},
+        enums::{DSPIcon, DSPItem, DSPRecipe},
+        families::available_families,
         traits::{DSPEnum, TryFromUserString},
         visit::Visitor,
     },
-    edit::{stats::GetStats, EditBlueprint},
-    error::some_error,
-    locale,
+    edit::{
+        select::Selector,
+        stats::GetStats,
+        upgrade::{Direction, TierChange},
+        EditBlueprint,
+    },
+    error::{some_error, Error},
+    locale::{self, Locale},
 };
 
 #[wasm_bindgen(start)]
@@ -20,6 +26,79 @@ pub fn init() {
     console_error_panic_hook::set_once();
 }
 
+/// Error thrown by all blueprint functions.
+///
+/// `kind` is one of "hash_mismatch", "bad_hash", "bad_prefix", "csv", "base64", "gzip",
+/// "decode" or "other". Decode errors also carry the byte offset and the position of the
+/// building that failed to decode, if known.
+#[wasm_bindgen]
+pub struct BlueprintError {
+    kind: &'static str,
+    message: String,
+    offset: Option<u32>,
+    building: Option<u32>,
+}
+
+#[wasm_bindgen]
+impl BlueprintError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.to_owned()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn offset(&self) -> Option<u32> {
+        self.offset
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn building(&self) -> Option<u32> {
+        self.building
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl From<anyhow::Error> for BlueprintError {
+    fn from(e: anyhow::Error) -> Self {
+        let message = format!("{:#}", e);
+        match e.downcast_ref::<Error>() {
+            Some(Error::Decode {
+                offset, building, ..
+            }) => Self {
+                kind: "decode",
+                message,
+                offset: Some(*offset as u32),
+                building: building.map(|b| b.position as u32),
+            },
+            Some(err) => Self {
+                kind: err.kind(),
+                message,
+                offset: None,
+                building: None,
+            },
+            None => Self {
+                kind: "other",
+                message,
+                offset: None,
+                building: None,
+            },
+        }
+    }
+}
+
+fn js_error(e: anyhow::Error) -> JsValue {
+    BlueprintError::from(e).into()
+}
+
 fn parse_comma_list(s: &str) -> anyhow::Result<Vec<(String, String)>> {
     s.split(',')
         .filter(|v| !v.trim().is_empty())
@@ -82,9 +161,37 @@ pub fn edit_blueprint(
     replace_recipe: &str,
     replace_both: &str,
     compression_level: u32,
+) -> Result<String, JsValue> {
+    edit_blueprint_where(
+        bp_string,
+        "",
+        replace_building,
+        replace_item,
+        replace_recipe,
+        replace_both,
+        compression_level,
+    )
+}
+
+/// Like `edit_blueprint`, only editing buildings that match `selector`,
+/// e.g. "item=ArcSmelter and area=0". An empty selector edits everything.
+#[wasm_bindgen]
+pub fn edit_blueprint_where(
+    bp_string: &str,
+    selector: &str,
+    replace_building: &str,
+    replace_item: &str,
+    replace_recipe: &str,
+    replace_both: &str,
+    compression_level: u32,
 ) -> Result<String, JsValue> {
     let inner = || -> anyhow::Result<String> {
         let mut bp = EditBlueprint::new(Blueprint::new(bp_string)?);
+        let selector = match selector.trim() {
+            "" => None,
+            s => Some(s.parse::<Selector>()?),
+        };
+        let selector = selector.as_ref();
 
         let mut item_replace: HashMap<DSPItem, DSPItem> = HashMap::new();
         let mut recipe_replace: HashMap<DSPRecipe, DSPRecipe> = HashMap::new();
@@ -114,19 +221,19 @@ pub fn edit_blueprint(
         }
 
         if !item_replace.is_empty() {
-            bp.replace_item(item_replace);
+            bp.replace_item_where(item_replace, selector);
         }
         if !recipe_replace.is_empty() {
-            bp.replace_recipe(recipe_replace);
+            bp.replace_recipe_where(recipe_replace, selector);
         }
         if !building_replace.is_empty() {
-            bp.replace_building(building_replace)?;
+            bp.replace_building_where(building_replace, selector)?;
         }
 
         bp.0.into_bp_string(compression_level)
     };
 
-    inner().map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    inner().map_err(js_error)
 }
 
 /// Return a JSON string describing the buildings in the blueprint.
@@ -139,7 +246,7 @@ pub fn blueprint_info(bp_string: &str) -> Result<String, JsValue> {
         stats.visit_blueprint(&mut bp);
         Ok(format!("{}", stats.0))
     };
-    inner().map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    inner().map_err(js_error)
 }
 
 /// Return building counts as a JSON array: [{"id": 2303, "count": 5}, ...]
@@ -167,82 +274,127 @@ pub fn blueprint_building_counts(bp_string: &str) -> Result<String, JsValue> {
             .collect();
         Ok(format!("[{}]", json_entries.join(",")))
     };
-    inner().map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    inner().map_err(js_error)
+}
+
+/// Recompute the hash of a blueprint string without parsing it.
+/// Replaces the existing hash, or appends one to an unsigned blueprint.
+#[wasm_bindgen]
+pub fn sign_blueprint(bp_string: &str) -> Result<String, JsValue> {
+    Blueprint::sign(bp_string).map_err(js_error)
 }
 
-/// Return a JSON string describing all supported upgrade groups.
-/// This is a static definition, used by the frontend to render the UI.
+/// Check the hash of a blueprint string without parsing it.
+/// Returns false on a hash mismatch; throws if the blueprint has no valid hash.
+#[wasm_bindgen]
+pub fn check_blueprint_hash(bp_string: &str) -> Result<bool, JsValue> {
+    match Blueprint::check_hash(bp_string) {
+        Ok(()) => Ok(true),
+        Err(Error::HashMismatch { .. }) => Ok(false),
+        Err(e) => Err(js_error(e.into())),
+    }
+}
+
+/// Return a JSON string describing the available upgrade families (those with building models),
+/// used by the frontend to render the UI.
+/// Format: [{"id": "belt", "label": "...", "members": [{"id": "ConveyorBeltMKI", "label": "传送带"}, ...]}, ...]
 #[wasm_bindgen]
 pub fn upgrade_groups() -> String {
-    // Each group: { id, label, members: [{id, label}] }
-    r#"[
-  {
-    "id": "belt",
-    "label": "传送带 (Conveyor Belt)",
-    "members": [
-      {"id": "ConveyorBeltMKI",   "label": "传送带 Mk.I"},
-      {"id": "ConveyorBeltMKII",  "label": "传送带 Mk.II"},
-      {"id": "ConveyorBeltMKIII", "label": "传送带 Mk.III"}
-    ]
-  },
-  {
-    "id": "sorter",
-    "label": "分拣器 (Sorter)",
-    "members": [
-      {"id": "SorterMKI",   "label": "分拣器 Mk.I"},
-      {"id": "SorterMKII",  "label": "分拣器 Mk.II"},
-      {"id": "SorterMKIII", "label": "分拣器 Mk.III"},
-      {"id": "SorterMKIV",  "label": "集装分拣器"}
-    ]
-  },
-  {
-    "id": "assembler",
-    "label": "制造台 (Assembler)",
-    "members": [
-      {"id": "AssemblingMachineMkI",    "label": "制造台 Mk.I"},
-      {"id": "AssemblingMachineMkII",   "label": "制造台 Mk.II"},
-      {"id": "AssemblingMachineMkIII",  "label": "制造台 Mk.III"},
-      {"id": "RecomposingAssembler",    "label": "重组式制造台"}
-    ]
-  },
-  {
-    "id": "smelter",
-    "label": "熔炉 (Smelter)",
-    "members": [
-      {"id": "ArcSmelter",        "label": "电弧熔炉"},
-      {"id": "PlaneSmelter",      "label": "位面熔炉"},
-      {"id": "NegentropySmelter", "label": "负熵熔炉"}
-    ]
-  },
-  {
-    "id": "chemplant",
-    "label": "化工厂 (Chemical Plant)",
-    "members": [
-      {"id": "ChemicalPlant",        "label": "化工厂"},
-      {"id": "QuantumChemicalPlant", "label": "量子化工厂"}
-    ]
-  },
-  {
-    "id": "lab",
-    "label": "研究站 (Matrix Lab)",
-    "members": [
-      {"id": "MatrixLab",        "label": "矩阵研究站"},
-      {"id": "SelfevolutionLab", "label": "自演化研究站"}
-    ]
-  }
-]"#.to_owned()
+    let groups: Vec<serde_json::Value> = available_families()
+        .map(|f| {
+            let members: Vec<serde_json::Value> = f
+                .tiers
+                .iter()
+                .map(|t| {
+                    serde_json::json!({
+                        "id": t.as_ref(),
+                        "label": locale::name_in(t, Locale::cn).unwrap_or(t.as_ref()),
+                    })
+                })
+                .collect();
+            serde_json::json!({ "id": f.id, "label": f.label, "members": members })
+        })
+        .collect();
+    serde_json::Value::from(groups).to_string()
+}
+
+/// Move buildings matching `selector` up (or, if `down`, down) their upgrade families.
+///
+/// `tier` counts from 1 within each family; 0 means the highest tier, or the lowest when
+/// downgrading. `families` is a comma separated list of family ids; empty means all.
+///
+/// Returns JSON: {"blueprint": "...", "changed": [{"from": 2001, "to": 2003, "count": 4}],
+/// "unmapped": [...]}. Unmapped buildings were left alone because the replacement has no
+/// known model.
+#[wasm_bindgen]
+pub fn change_tiers(
+    bp_string: &str,
+    down: bool,
+    tier: u32,
+    families: &str,
+    selector: &str,
+    compression_level: u32,
+) -> Result<String, JsValue> {
+    let inner = || -> anyhow::Result<String> {
+        let mut bp = EditBlueprint::new(Blueprint::new(bp_string)?);
+        let selector = match selector.trim() {
+            "" => None,
+            s => Some(s.parse::<Selector>()?),
+        };
+        let change = TierChange {
+            direction: if down { Direction::Down } else { Direction::Up },
+            tier: (tier != 0).then_some(tier as usize),
+            families: families
+                .split(',')
+                .map(|f| f.trim().to_owned())
+                .filter(|f| !f.is_empty())
+                .collect(),
+        };
+        let report = bp.change_tiers(&change, selector.as_ref())?;
+        let list = |l: &[(DSPItem, DSPItem, usize)]| -> Vec<serde_json::Value> {
+            l.iter()
+                .map(|(from, to, count)| {
+                    serde_json::json!({
+                        "from": u16::from(*from),
+                        "to": u16::from(*to),
+                        "count": count,
+                    })
+                })
+                .collect()
+        };
+        Ok(serde_json::json!({
+            "blueprint": bp.0.into_bp_string(compression_level)?,
+            "changed": list(&report.changed),
+            "unmapped": list(&report.unmapped),
+        })
+        .to_string())
+    };
+    inner().map_err(js_error)
 }
 
-/// Return a JSON array of all in-game items with their IDs and Chinese names.
+/// Return a JSON array of all in-game items with their IDs and names in a locale, Chinese by
+/// default. Names missing from the locale are in English.
 /// Format: [{"id": 1001, "name": "铁矿"}, ...]
 #[wasm_bindgen]
-pub fn item_list() -> String {
-    let items = locale::all_items_cn();
-    let entries: Vec<String> = items
-        .iter()
-        .map(|(id, name)| format!("{{\"id\":{},\"name\":{}}}", id, serde_json::to_string(name).unwrap()))
-        .collect();
-    format!("[{}]", entries.join(","))
+pub fn item_list(locale: Option<String>) -> Result<String, JsValue> {
+    let inner = || -> anyhow::Result<String> {
+        let locale = match locale {
+            None => Locale::cn,
+            Some(l) => Locale::try_from_user_string(&l)?,
+        };
+        let entries: Vec<String> = locale::all_items(locale)
+            .iter()
+            .map(|(id, name)| {
+                format!(
+                    "{{\"id\":{},\"name\":{}}}",
+                    id,
+                    serde_json::to_string(name).unwrap()
+                )
+            })
+            .collect();
+        Ok(format!("[{}]", entries.join(",")))
+    };
+    inner().map_err(js_error)
 }
 
 /// Get the 5 blueprint icon slots as a JSON array of u32 values.
@@ -254,12 +406,13 @@ pub fn get_blueprint_icons(bp_string: &str) -> Result<String, JsValue> {
         let icons: Vec<u32> = bp.icons.to_vec();
         Ok(serde_json::to_string(&icons)?)
     };
-    inner().map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    inner().map_err(js_error)
 }
 
 /// Set individual blueprint icon slots.
 /// `icons_json` is a JSON array of up to 5 objects: [{"slot": 0, "value": 1001}, ...]
-/// slot is 0-4; value uses DSP encoding (item id, or recipe id + 20000, or 0 to clear).
+/// slot is 0-4; value uses DSP encoding (item id, or recipe id + 20000, or 0 to clear), or is
+/// an icon name like "IronIngot", "recipe:Gear" or "signal:405".
 #[wasm_bindgen]
 pub fn set_blueprint_icons(
     bp_string: &str,
@@ -273,9 +426,13 @@ pub fn set_blueprint_icons(
             let slot = item["slot"]
                 .as_u64()
                 .ok_or_else(|| crate::error::some_error("missing \"slot\" field"))? as usize;
-            let value = item["value"]
-                .as_u64()
-                .ok_or_else(|| crate::error::some_error("missing \"value\" field"))? as u32;
+            let value = match &item["value"] {
+                serde_json::Value::String(name) => DSPIcon::try_from_user_string(name)?.into(),
+                v => v
+                    .as_u64()
+                    .ok_or_else(|| crate::error::some_error("missing \"value\" field"))?
+                    as u32,
+            };
             if slot >= 5 {
                 anyhow::bail!("icon slot {} out of range (0-4)", slot);
             }
@@ -289,16 +446,86 @@ pub fn set_blueprint_icons(
         }
         bp.into_bp_string(compression_level)
     };
-    inner().map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    inner().map_err(js_error)
+}
+
+/// Return blueprint metadata as JSON:
+/// {"icon_text": "...", "description": "...", "timestamp": "2024-01-31 12:30:00", "layout": 10}
+#[wasm_bindgen]
+pub fn get_blueprint_metadata(bp_string: &str) -> Result<String, JsValue> {
+    let inner = || -> anyhow::Result<String> {
+        let bp = Blueprint::new(bp_string)?;
+        Ok(serde_json::json!({
+            "icon_text": bp.get_icon_text()?,
+            "description": bp.get_description()?,
+            "timestamp": bp.get_timestamp(),
+            "layout": bp.layout,
+        })
+        .to_string())
+    };
+    inner().map_err(js_error)
+}
+
+/// Set blueprint metadata. `metadata_json` has any of the fields `get_blueprint_metadata`
+/// returns; missing fields are left alone. `timestamp` is a date like "2024-01-31 12:30" or a
+/// raw tick count.
+#[wasm_bindgen]
+pub fn set_blueprint_metadata(
+    bp_string: &str,
+    metadata_json: &str,
+    compression_level: u32,
+) -> Result<String, JsValue> {
+    let inner = || -> anyhow::Result<String> {
+        let mut bp = Blueprint::new(bp_string)?;
+        let meta: serde_json::Value = serde_json::from_str(metadata_json)?;
+        let text = |field: &str| -> anyhow::Result<Option<String>> {
+            match &meta[field] {
+                serde_json::Value::Null => Ok(None),
+                serde_json::Value::String(s) => Ok(Some(s.clone())),
+                serde_json::Value::Number(n) if field == "timestamp" => Ok(Some(n.to_string())),
+                _ => anyhow::bail!("\"{}\" must be a string", field),
+            }
+        };
+        if let Some(t) = text("icon_text")? {
+            bp.set_icon_text(&t);
+        }
+        if let Some(d) = text("description")? {
+            bp.set_description(&d);
+        }
+        if let Some(t) = text("timestamp")? {
+            bp.set_timestamp(&t)?;
+        }
+        match &meta["layout"] {
+            serde_json::Value::Null => (),
+            v => bp.set_layout(
+                v.as_u64()
+                    .and_then(|l| u32::try_from(l).ok())
+                    .ok_or_else(|| crate::error::some_error("\"layout\" must be a number"))?,
+            ),
+        }
+        bp.into_bp_string(compression_level)
+    };
+    inner().map_err(js_error)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_upgrade_groups_follow_families() {
+        let groups: Vec<serde_json::Value> = serde_json::from_str(&upgrade_groups()).unwrap();
+        assert_eq!(groups.len(), available_families().count());
+        assert!(groups.iter().all(|g| g["id"] != "lab"));
+        let belt = &groups[0];
+        assert_eq!(belt["id"], "belt");
+        assert_eq!(belt["members"][2]["id"], "ConveyorBeltMKIII");
+        assert_eq!(belt["members"][0]["label"], "传送带");
+    }
+
     #[test]
     fn test_item_list_json_structure() {
-        let json = item_list();
+        let json = item_list(None).unwrap();
         // Should be a JSON array of objects with "id" and "name" fields.
         let parsed: Vec<serde_json::Value> =
             serde_json::from_str(&json).expect("item_list should return valid JSON");
@@ -316,6 +543,13 @@ mod tests {
                 "each item should have string 'name'"
             );
         }
+        let iron_ore = |list: &[serde_json::Value]| {
+            list.iter().find(|e| e["id"] == 1001).unwrap()["name"].clone()
+        };
+        assert_eq!(iron_ore(&parsed), "铁矿");
+        let en: Vec<serde_json::Value> =
+            serde_json::from_str(&item_list(Some("en".to_owned())).unwrap()).unwrap();
+        assert_eq!(iron_ore(&en), "Iron Ore");
     }
 
     /// Tests for get_blueprint_icons / set_blueprint_icons use JsValue which only
@@ -366,6 +600,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_error_kind_for_bad_hash() {
+        let e =
+            crate::blueprint::Blueprint::new("NOTABLUEPRINT:\"00000000000000000000000000000000")
+                .err()
+                .unwrap();
+        let e = BlueprintError::from(e);
+        assert_eq!(e.kind, "hash_mismatch");
+        assert!(e.offset.is_none());
+    }
+
     #[test]
     fn test_set_icons_rejects_invalid_json() {
         let bad: Result<Vec<serde_json::Value>, _> = serde_json::from_str("this is not json");
@@ -379,4 +624,22 @@ mod tests {
         let slot = updates[0]["slot"].as_u64().unwrap() as usize;
         assert!(slot >= 5, "slot 5 should be detected as out of range (0-4)");
     }
+
+    #[test]
+    fn test_set_and_get_metadata() {
+        let f = crate::testutil::get_file("旧版本5飞机.txt");
+        let bp = std::str::from_utf8(&f).unwrap().trim();
+        let bp = set_blueprint_metadata(
+            bp,
+            r#"{"description": "a, \"b\"", "timestamp": "2024-01-31", "layout": 10}"#,
+            6,
+        )
+        .unwrap();
+        let meta: serde_json::Value =
+            serde_json::from_str(&get_blueprint_metadata(&bp).unwrap()).unwrap();
+        assert_eq!(meta["description"], "a, \"b\"");
+        assert_eq!(meta["timestamp"], "2024-01-31 00:00:00");
+        assert_eq!(meta["layout"], 10);
+        assert_eq!(meta["icon_text"], "五飞机_v1.1");
+    }
 }
@@ -1,20 +1,16 @@
 #!/usr/bin/env python3
 
-# First, load items and recipes.
+# First, load items and recipes. English names define the enums; every other directory in data/
+# is a locale that names some or all of them. Missing names fall back to English at runtime.
+# The build does not read data/, so rerun this script and commit its output after adding a locale.
 
-class Triple:
-    def __init__(self, en_name, cn_name):
-        number, en_name = en_name.strip().split(maxsplit=1)
-        number_2, cn_name = cn_name.strip().split(maxsplit=1)
-
-        number = int(number)
-        number_2 = int(number_2)
-        assert(number == number_2)
+import os
 
+class Entry:
+    def __init__(self, number, en_name):
         self.number = number
         self.en_name = en_name
-        self.cn_name = cn_name
-        self.id = Triple._make_id(en_name)
+        self.id = Entry._make_id(en_name)
 
     def _make_id(en_name):
         def niceify(s):
@@ -26,41 +22,85 @@ class Triple:
         return "".join(map(niceify, en_name.split()))
 
 
-en_items = open("data/en/items.txt").readlines()
-cn_items = open("data/cn/items.txt").readlines()
-items = list(map(lambda x: Triple(x[0], x[1]), zip(en_items, cn_items)))
-
-en_recipes = open("data/en/recipes.txt").readlines()
-cn_recipes = open("data/cn/recipes.txt").readlines()
-recipes = list(map(lambda x: Triple(x[0], x[1]), zip(en_recipes, cn_recipes)))
+def load_names(file):
+    names = {}
+    if not os.path.exists(file):
+        return names
+    for line in open(file):
+        if not line.strip() or line.startswith("#"):
+            continue
+        number, name = line.strip().split(maxsplit=1)
+        names[int(number)] = name
+    return names
+
+locales = ["en"] + sorted(l for l in os.listdir("data")
+    if l != "en" and os.path.exists(f"data/{l}/items.txt"))
+for l in locales:
+    assert l.isidentifier() and l.islower(), f"Locale directory name {l} is not a valid identifier"
+
+item_names = {l: load_names(f"data/{l}/items.txt") for l in locales}
+recipe_names = {l: load_names(f"data/{l}/recipes.txt") for l in locales}
+signal_names = {l: load_names(f"data/{l}/signals.txt") for l in locales}
+tech_names = {l: load_names(f"data/{l}/techs.txt") for l in locales}
+
+items = [Entry(number, name) for number, name in item_names["en"].items()]
+recipes = [Entry(number, name) for number, name in recipe_names["en"].items()]
+signals = [Entry(number, name) for number, name in signal_names["en"].items()]
+techs = [Entry(number, name) for number, name in tech_names["en"].items()]
 
 # Now, paste them in the proper location in src/data/enums.rs. We have comment markers to make it work.
 
 enums_source = open("src/data/enums.rs")
 
-def replace_enums(lines, items, enum_name):
-    enum_start = f"//{enum_name} enum start"
-    enum_end = f"//{enum_name} enum end"
+def replace_between(lines, name, new_lines):
+    start = f"//{name} start"
+    end = f"//{name} end"
     output = []
     state = "before"
     for line in lines:
         if state == "before":
             output += [line]
-            if enum_start in line:
+            if start in line:
                 state = "within"
-                for i in items:
-                    output += [f"    {i.id} = {i.number},\n"]
+                output += new_lines
                 continue
         elif state == "within":
-            if enum_end in line:
+            if end in line:
                 output += [line]
                 state = "after"
         else:
             output += [line]
     return output
 
+def replace_enums(lines, items, enum_name):
+    return replace_between(lines, f"{enum_name} enum", [f"    {i.id} = {i.number},\n" for i in items])
+
 enums_source = replace_enums(enums_source, items, "DSPItem")
 enums_source = replace_enums(enums_source, recipes, "DSPRecipe")
+enums_source = replace_enums(enums_source, signals, "DSPSignal")
+enums_source = replace_enums(enums_source, techs, "DSPTech")
+
+# Building models. Items that share a model map back to the first of them.
+
+item_ids = {i.number: i.id for i in items}
+models = []
+for line in open("data/models.txt"):
+    line = line.split("#")[0].strip()
+    if not line:
+        continue
+    number, model = map(int, line.split())
+    models.append((item_ids[number], model))
+
+model_names = {}
+for item, model in models:
+    model_names.setdefault(model, item)
+
+enums_source = replace_between(enums_source, "BPModel enum",
+    [f"    {name} = {model},\n" for model, name in model_names.items()])
+enums_source = replace_between(enums_source, "BPModel from_building",
+    [f"            DSPItem::{item} => Self::{model_names[model]},\n" for item, model in models])
+enums_source = replace_between(enums_source, "BPModel building",
+    [f"            Self::{name} => DSPItem::{name},\n" for name in model_names.values()])
 
 enums_file = open("src/data/enums.rs", "w")
 for line in enums_source:
@@ -69,15 +109,38 @@ for line in enums_source:
 
 # Now, create localizations.
 
-def make_locale(file, items, enum_name, get_name):
+def make_locale(file, items, enum_name, names):
+    os.makedirs(os.path.dirname(file), exist_ok=True)
     f = open(file, "w")
     f.write("&[\n")
     for i in items:
-        f.write(f"    ({enum_name}::{i.id}, \"{get_name(i)}\"),\n")
+        if i.number in names:
+            name = names[i.number].replace("\\", "\\\\").replace("\"", "\\\"")
+            f.write(f"    ({enum_name}::{i.id}, \"{name}\"),\n")
     f.write("]\n")
     f.close()
 
-make_locale("src/locale/data/en/items.rs", items, "DSPItem", lambda i: i.en_name)
-make_locale("src/locale/data/cn/items.rs", items, "DSPItem", lambda i: i.cn_name)
-make_locale("src/locale/data/en/recipes.rs", recipes, "DSPRecipe", lambda i: i.en_name)
-make_locale("src/locale/data/cn/recipes.rs", recipes, "DSPRecipe", lambda i: i.cn_name)
+# Models are named after the building that uses them.
+
+items_by_id = {i.id: i for i in items}
+model_items = [items_by_id[name] for name in model_names.values()]
+
+for l in locales:
+    make_locale(f"src/locale/data/{l}/items.rs", items, "DSPItem", item_names[l])
+    make_locale(f"src/locale/data/{l}/recipes.rs", recipes, "DSPRecipe", recipe_names[l])
+    make_locale(f"src/locale/data/{l}/models.rs", model_items, "BPModel", item_names[l])
+    make_locale(f"src/locale/data/{l}/signals.rs", signals, "DSPSignal", signal_names[l])
+    make_locale(f"src/locale/data/{l}/techs.rs", techs, "DSPTech", tech_names[l])
+
+# Register the locales in src/locale/mod.rs.
+
+locale_source = open("src/locale/mod.rs")
+locale_source = replace_between(locale_source, "Locale enum", [f"    {l},\n" for l in locales])
+for name, file in [("DSP_ITEM_LLIST", "items"), ("DSP_RECIPE_LLIST", "recipes"), ("DSP_MODEL_LLIST", "models"),
+        ("DSP_SIGNAL_LLIST", "signals"), ("DSP_TECH_LLIST", "techs")]:
+    locale_source = replace_between(locale_source, name,
+        [f"    LList(Locale::{l}, include!(\"data/{l}/{file}.rs\")),\n" for l in locales])
+
+locale_file = open("src/locale/mod.rs", "w")
+for line in locale_source:
+    locale_file.write(line)
@@ -16,6 +16,32 @@ export interface UpgradeGroup {
   members: UpgradeMember[]
 }
 
+export interface TierChangeEntry {
+  from: number
+  to: number
+  count: number
+}
+
+export interface TierChangeResult {
+  blueprint: string
+  changed: TierChangeEntry[]
+  /** Buildings left alone because the replacement has no known model. */
+  unmapped: TierChangeEntry[]
+}
+
+/**
+ * Error object thrown by every blueprint function.
+ * `String(e)` gives the full message.
+ */
+export interface BlueprintError {
+  kind: 'hash_mismatch' | 'bad_hash' | 'bad_prefix' | 'csv' | 'base64' | 'gzip' | 'decode' | 'other'
+  message: string
+  /** Byte offset into the decompressed data, for decode errors. */
+  offset?: number
+  /** Position of the building that failed to decode, if known. */
+  building?: number
+}
+
 export interface WasmModule {
   edit_blueprint(
     bp_string: string,
@@ -25,8 +51,30 @@ export interface WasmModule {
     replace_both: string,
     compression_level: number
   ): string
+  /** Like edit_blueprint, only for buildings matching `selector`, e.g. "item=ArcSmelter and area=0". */
+  edit_blueprint_where(
+    bp_string: string,
+    selector: string,
+    replace_building: string,
+    replace_item: string,
+    replace_recipe: string,
+    replace_both: string,
+    compression_level: number
+  ): string
   blueprint_info(bp_string: string): string
   upgrade_groups(): string
+  /**
+   * Moves buildings up (or, if `down`, down) their upgrade families. `tier` counts from 1;
+   * 0 means the highest (or lowest) tier. Returns JSON, see `TierChangeResult`.
+   */
+  change_tiers(
+    bp_string: string,
+    down: boolean,
+    tier: number,
+    families: string,
+    selector: string,
+    compression_level: number
+  ): string
   /** Returns a JSON array of 5 u32 icon values for the blueprint. */
   get_blueprint_icons(bp_string: string): string
   /**
@@ -40,14 +88,37 @@ export interface WasmModule {
    * Items: value = item_id (1001-19999)
    * Recipes: value = recipe_id + 20000
    * Clear slot: value = 0
+   * value can also be a name: "IronIngot", "recipe:Gear", "signal:405", "tech:1001"
    */
   set_blueprint_icons(
     bp_string: string,
     icons_json: string,
     compression_level: number
   ): string
-  /** Returns JSON array [{id: number, name: string}, ...] of all items (Chinese names). */
-  item_list(): string
+  /**
+   * Returns blueprint metadata as JSON:
+   * {"icon_text": string, "description": string, "timestamp": "YYYY-MM-DD HH:MM:SS", "layout": number}
+   */
+  get_blueprint_metadata(bp_string: string): string
+  /**
+   * Updates blueprint metadata. `metadata_json` has any of the fields get_blueprint_metadata
+   * returns; missing fields are left alone. timestamp can also be a date like "2024-01-31 12:30"
+   * or a raw tick count.
+   */
+  set_blueprint_metadata(
+    bp_string: string,
+    metadata_json: string,
+    compression_level: number
+  ): string
+  /** Recomputes the blueprint hash without parsing it. */
+  sign_blueprint(bp_string: string): string
+  /** Returns false if the blueprint hash does not match. */
+  check_blueprint_hash(bp_string: string): boolean
+  /**
+   * Returns JSON array [{id: number, name: string}, ...] of all items, with names in `locale`
+   * (Chinese by default, English where the locale has no name).
+   */
+  item_list(locale?: string): string
 }
 
 let _wasm: WasmModule | null = null